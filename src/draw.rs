@@ -3,12 +3,13 @@ use std::f64::consts::PI;
 use crate::{
     colors::Color,
     math::{Matrix, Vector3},
-    obj::ObjFile,
+    mesh::{BoundingSphere, FitTransform},
+    obj::{ObjFile, SubMeshFilter},
     tga::{ColorSpace, Grayscale, Image, RGBA},
     triangle::Triangle,
     types::Point,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 
 fn rotate(vec: &Vector3<f64>) -> Vector3<f64> {
     let a = PI / 6.;
@@ -26,53 +27,239 @@ fn rotate(vec: &Vector3<f64>) -> Vector3<f64> {
 fn project(vec: Vector3<f64>, width: f64, height: f64) -> Vector3<isize> {
     Vector3::new([
         ((vec.x() + 1.0) * 0.5 * width).min(width - 1.0) as isize,
-        ((vec.y() + 1.0) * 0.5 * height).min(width - 1.0) as isize,
+        ((vec.y() + 1.0) * 0.5 * height).min(height - 1.0) as isize,
         ((vec.z() + 1.0) * (255. / 2.)) as isize,
     ])
 }
 
+const CAMERA_DISTANCE: f64 = 3.;
+
 fn perspective(vec: Vector3<f64>) -> Vector3<f64> {
-    let camera = 3.;
-    let scalar = 1. - vec.z() / camera;
+    let scalar = 1. - vec.z() / CAMERA_DISTANCE;
     vec / scalar
 }
 
+// Largest sphere at the origin whose perspective projection still fits in -1..1
+fn view_radius() -> f64 {
+    CAMERA_DISTANCE / (CAMERA_DISTANCE * CAMERA_DISTANCE + 1.).sqrt()
+}
+
+// Lines and points sitting on a surface shouldn't lose the depth test to it
+const DEPTH_BIAS: f64 = 1.0;
+
+#[derive(Debug, Clone)]
+pub struct DrawOptions {
+    pub sub_meshes: SubMeshFilter,
+    // Used for "l" and "p" elements when the file has no vertex colors
+    pub line_color: Color,
+    // Width in pixels of the square drawn for each "p" vertex
+    pub point_size: usize,
+    // Centers and scales the mesh so it fills the image, whatever its coordinates.
+    // When off, verticies are expected to lie in -1..1.
+    pub fit_to_view: bool,
+    // Writes the depth buffer to z_buffer.tga in the working directory
+    pub dump_z_buffer: bool,
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        DrawOptions {
+            sub_meshes: SubMeshFilter::All,
+            line_color: Color::Yellow,
+            point_size: 3,
+            fit_to_view: true,
+            dump_z_buffer: false,
+        }
+    }
+}
+
+fn to_color_space<T: ColorSpace>(color: &Color) -> T {
+    let rgba = color.rgba_value();
+    T::from_bgra(rgba.b, rgba.g, rgba.r, rgba.a)
+}
+
+// RGB in 0..1
+fn blend<T: ColorSpace>(one: &Vector3<f64>, two: &Vector3<f64>, t: f64) -> T {
+    let channel = |i: usize| {
+        let value = one[i] + t * (two[i] - one[i]);
+        (value.clamp(0., 1.) * 255.).round() as u8
+    };
+    T::from_bgra(channel(2), channel(1), channel(0), 255)
+}
+
+// Only draws where nothing closer is in the z-buffer. Pixels off the image are skipped.
+fn depth_test_pixel<T: ColorSpace + Copy>(
+    x: isize,
+    y: isize,
+    z: f64,
+    color: T,
+    img: &mut Image<T>,
+    z_buff: &mut [Vec<f64>],
+) -> Result<()> {
+    let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+        return Ok(());
+    };
+    if x >= img.width || y >= img.height {
+        return Ok(());
+    }
+    if z + DEPTH_BIAS >= z_buff[x][y] {
+        z_buff[x][y] = z_buff[x][y].max(z);
+        img.set_pixel(x, y, color)?;
+    }
+    Ok(())
+}
+
+// Same stepping as draw_line, with depth interpolated along the line.
+// `shade` gets how far along the line the pixel is, 0 at `one` and 1 at `two`.
+fn draw_line_depth_tested<T: ColorSpace + Copy>(
+    one: &Vector3<isize>,
+    two: &Vector3<isize>,
+    img: &mut Image<T>,
+    z_buff: &mut [Vec<f64>],
+    shade: impl Fn(f64) -> T,
+) -> Result<()> {
+    let steep = one.y().abs_diff(two.y()) > one.x().abs_diff(two.x());
+    let (one_x, one_y, two_x, two_y) = if steep {
+        (one.y(), one.x(), two.y(), two.x())
+    } else {
+        (one.x(), one.y(), two.x(), two.y())
+    };
+    for x in one_x.min(two_x)..=one_x.max(two_x) {
+        let t = if one_x == two_x {
+            0.
+        } else {
+            (x - one_x) as f64 / (two_x - one_x) as f64
+        };
+        let y = (one_y as f64 + t * (two_y - one_y) as f64).round() as isize;
+        let z = one.z() as f64 + t * (two.z() - one.z()) as f64;
+        let (pixel_x, pixel_y) = if steep { (y, x) } else { (x, y) };
+        depth_test_pixel(pixel_x, pixel_y, z, shade(t), img, z_buff)?;
+    }
+    Ok(())
+}
+
+fn draw_point_depth_tested<T: ColorSpace + Copy>(
+    point: &Vector3<isize>,
+    size: usize,
+    color: T,
+    img: &mut Image<T>,
+    z_buff: &mut [Vec<f64>],
+) -> Result<()> {
+    let size = size.max(1) as isize;
+    let start = -(size - 1) / 2;
+    for dx in start..start + size {
+        for dy in start..start + size {
+            depth_test_pixel(
+                point.x() + dx,
+                point.y() + dy,
+                point.z() as f64,
+                color,
+                img,
+                z_buff,
+            )?;
+        }
+    }
+    Ok(())
+}
+
 pub fn draw_obj_file<T: ColorSpace + Copy>(obj: ObjFile, img: &mut Image<T>) -> Result<()> {
+    draw_obj_file_with_options(obj, img, &DrawOptions::default())
+}
+
+pub fn draw_obj_file_with_options<T: ColorSpace + Copy>(
+    obj: ObjFile,
+    img: &mut Image<T>,
+    options: &DrawOptions,
+) -> Result<()> {
+    let face_mask = obj.face_mask(&options.sub_meshes);
     let width = img.width;
     let height = img.height;
     let verticies = obj.verticies;
-    let faces = obj.faces;
-    let mut z_buff = vec![vec![0.; width]; height];
+    let triangles = obj.triangles;
+    let vertex_colors = obj.vertex_colors;
+    // Indexed [x][y], same as Triangle::rasterize
+    let mut z_buff = vec![vec![0.; height]; width];
     let width_f64 = width as f64;
     let height_f64 = height as f64;
 
-    for face in faces {
-        if let (Some(vertex_one), Some(vertex_two), Some(vertex_three)) = (
-            verticies.get(face.one - 1),
-            verticies.get(face.two - 1),
-            verticies.get(face.three - 1),
-        ) {
-            let triangle = Triangle {
-                vector_a: project(perspective(rotate(vertex_one)), width_f64, height_f64),
-                vector_b: project(perspective(rotate(vertex_two)), width_f64, height_f64),
-                vector_c: project(perspective(rotate(vertex_three)), width_f64, height_f64),
-            };
+    let fit = options
+        .fit_to_view
+        .then(|| BoundingSphere::from_points(&verticies))
+        .flatten()
+        .map(|sphere| FitTransform::to_sphere(&sphere, view_radius()));
 
-            // z index hack
-            if triangle.area() > 1.0 {
+    let screen_vertex = |index: usize| -> Result<Vector3<isize>> {
+        let vertex = verticies
+            .get(index)
+            .ok_or_else(|| anyhow!("Vertex index {} out of range", index))?;
+        let vertex = match &fit {
+            Some(fit) => rotate(&fit.apply(vertex)),
+            None => rotate(vertex),
+        };
+        Ok(project(perspective(vertex), width_f64, height_f64))
+    };
+
+    for face in triangles {
+        if face_mask.get(face.face) != Some(&true) {
+            continue;
+        }
+        let triangle = Triangle {
+            vector_a: screen_vertex(face.one.vertex)?,
+            vector_b: screen_vertex(face.two.vertex)?,
+            vector_c: screen_vertex(face.three.vertex)?,
+        };
+
+        // z index hack
+        if triangle.area() > 1.0 {
+            let colors =
+                [face.one, face.two, face.three].map(|corner| vertex_colors.get(corner.vertex));
+            if let [Some(one), Some(two), Some(three)] = colors {
+                triangle.draw_interpolated::<T>([one, two, three], img, Some(&mut z_buff))?;
+            } else {
                 triangle.draw::<T>(T::random(), img, Some(&mut z_buff))?;
             }
         }
     }
 
-    #[cfg(debug_assertions)]
-    draw_z_buffer(&z_buff, width, height);
+    // After the triangles so they can be depth tested against the surface
+    let line_color: T = to_color_space(&options.line_color);
+    for polyline in &obj.polylines {
+        for segment in polyline.verticies.windows(2) {
+            let (one, two) = (segment[0].vertex, segment[1].vertex);
+            let (screen_one, screen_two) = (screen_vertex(one)?, screen_vertex(two)?);
+            match (vertex_colors.get(one), vertex_colors.get(two)) {
+                (Some(color_one), Some(color_two)) => {
+                    draw_line_depth_tested(&screen_one, &screen_two, img, &mut z_buff, |t| {
+                        blend(color_one, color_two, t)
+                    })?
+                }
+                _ => draw_line_depth_tested(&screen_one, &screen_two, img, &mut z_buff, |_| {
+                    line_color
+                })?,
+            }
+        }
+    }
+    for &point in &obj.points {
+        let color = vertex_colors
+            .get(point)
+            .map_or(line_color, |color| blend(color, color, 0.));
+        draw_point_depth_tested(
+            &screen_vertex(point)?,
+            options.point_size,
+            color,
+            img,
+            &mut z_buff,
+        )?;
+    }
+
+    if options.dump_z_buffer {
+        draw_z_buffer(&z_buff, width, height);
+    }
 
     Ok(())
 }
 
-#[cfg(debug_assertions)]
-fn draw_z_buffer(z_buff: &Vec<Vec<f64>>, width: usize, height: usize) {
+fn draw_z_buffer(z_buff: &[Vec<f64>], width: usize, height: usize) {
     let mut z_buff_img = Image::<Grayscale>::new(width, height);
     for (i, row) in z_buff.iter().enumerate() {
         for (j, z_value_f64) in row.iter().enumerate() {
@@ -132,3 +319,77 @@ pub fn draw_line(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        draw::{draw_line_depth_tested, draw_obj_file, draw_point_depth_tested},
+        math::Vector3,
+        obj::{ObjParseOptions, parse_obj_str},
+        tga::{ColorSpace, Image, RGB},
+        triangle::Triangle,
+    };
+
+    fn rgb(img: &Image<RGB>, x: usize, y: usize) -> (u8, u8, u8) {
+        let pixel = img.get_pixel(x, y).unwrap();
+        (pixel.r, pixel.g, pixel.b)
+    }
+
+    #[test]
+    fn draw_lines_depth_tested() {
+        let mut img = Image::<RGB>::new(20, 20);
+        let mut z_buff = vec![vec![0.; 20]; 20];
+        let surface = RGB::from_bgra(0, 0, 255, 255);
+        let line = RGB::from_bgra(255, 0, 0, 255);
+        let triangle = Triangle {
+            vector_a: Vector3::new([0, 0, 100]),
+            vector_b: Vector3::new([19, 0, 100]),
+            vector_c: Vector3::new([0, 19, 100]),
+        };
+        triangle.draw(surface, &mut img, Some(&mut z_buff)).unwrap();
+
+        // Behind the triangle, then on it, then in front of it
+        let behind = [Vector3::new([2, 2, 50]), Vector3::new([2, 8, 50])];
+        let on = [Vector3::new([4, 2, 100]), Vector3::new([4, 8, 100])];
+        let front = [Vector3::new([6, 2, 150]), Vector3::new([6, 8, 150])];
+        for [one, two] in [&behind, &on, &front] {
+            draw_line_depth_tested(one, two, &mut img, &mut z_buff, |_| line).unwrap();
+        }
+        assert_eq!((255, 0, 0), rgb(&img, 2, 5));
+        assert_eq!((0, 0, 255), rgb(&img, 4, 5));
+        assert_eq!((0, 0, 255), rgb(&img, 6, 5));
+
+        // Partly off the image
+        let point = Vector3::new([19, 19, 0]);
+        draw_point_depth_tested(&point, 3, line, &mut img, &mut z_buff).unwrap();
+        assert_eq!((0, 0, 255), rgb(&img, 18, 18));
+        assert_eq!((0, 0, 255), rgb(&img, 19, 19));
+        assert_eq!((0, 0, 0), rgb(&img, 17, 17));
+    }
+
+    #[test]
+    fn draw_fits_mesh_to_view() {
+        // A red square far from the origin and much bigger than -1..1
+        let obj_str = "v 100 100 50 1 0 0\nv 140 100 50 1 0 0\nv 140 140 50 1 0 0\nv 100 140 50 1 0 0\nf 1 2 3 4\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let mut img = Image::<RGB>::new(40, 40);
+        draw_obj_file(obj, &mut img).unwrap();
+
+        assert_eq!((255, 0, 0), rgb(&img, 20, 20));
+        // Framed, not touching the borders
+        assert_eq!((0, 0, 0), rgb(&img, 0, 20));
+        assert_eq!((0, 0, 0), rgb(&img, 39, 20));
+    }
+
+    #[test]
+    fn draw_non_square_images() {
+        // A square with a line and points at its corners, drawn wide and tall
+        let obj_str = "v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4\nl 1 3\np 1 2 3 4\n";
+        for (width, height) in [(60, 20), (20, 60)] {
+            let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+            let mut img = Image::<RGB>::new(width, height);
+            draw_obj_file(obj, &mut img).unwrap();
+            assert_ne!((0, 0, 0), rgb(&img, width / 2, height / 2));
+        }
+    }
+}
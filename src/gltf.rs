@@ -0,0 +1,1004 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    math::{Matrix, Vector, Vector3},
+    obj::{MaterialRange, ObjFile, SubMesh, mtl::Material, mtl::Texture},
+    tga::{Image, RGBA},
+    types::{Face, FaceVertex, Polyline},
+};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_HEADER_LENGTH: usize = 12;
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+
+// Primitive modes
+const POINTS: u64 = 0;
+const LINES: u64 = 1;
+const LINE_LOOP: u64 = 2;
+const LINE_STRIP: u64 = 3;
+const TRIANGLES: u64 = 4;
+const TRIANGLE_STRIP: u64 = 5;
+const TRIANGLE_FAN: u64 = 6;
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>> {
+    let value = |byte: u8| -> Result<u32> {
+        Ok(match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(anyhow!("Invalid base64 character '{}'", byte as char)),
+        } as u32)
+    };
+    let bytes: Vec<u8> = encoded
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace() && *byte != b'=')
+        .collect();
+    let mut decoded = Vec::with_capacity(bytes.len() * 3 / 4);
+    for chunk in bytes.chunks(4) {
+        if chunk.len() == 1 {
+            return Err(anyhow!("Truncated base64 data"));
+        }
+        let mut bits = 0;
+        for (index, &byte) in chunk.iter().enumerate() {
+            bits |= value(byte)? << (18 - 6 * index);
+        }
+        let length = chunk.len() - 1;
+        decoded.extend_from_slice(&bits.to_be_bytes()[1..1 + length]);
+    }
+    Ok(decoded)
+}
+
+// "My%20Model.bin" -> "My Model.bin"
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Data URIs are decoded, anything else is a file next to the glTF
+fn load_uri(uri: &str, base_dir: &Path) -> Result<Vec<u8>> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data
+            .split_once(";base64,")
+            .ok_or_else(|| anyhow!("Only base64 data URIs are supported"))?;
+        return decode_base64(encoded);
+    }
+    let path = base_dir.join(decode_uri(uri));
+    std::fs::read(&path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))
+}
+
+fn get_usize(value: &Value, key: &str) -> Option<usize> {
+    value.get(key)?.as_u64().map(|number| number as usize)
+}
+
+fn get_floats<const N: usize>(value: &Value, key: &str) -> Option<[f64; N]> {
+    let array = value.get(key)?.as_array()?;
+    if array.len() != N {
+        return None;
+    }
+    let mut floats = [0.; N];
+    for (float, item) in floats.iter_mut().zip(array) {
+        *float = item.as_f64()?;
+    }
+    Some(floats)
+}
+
+fn get_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+// Split a .glb into its JSON and binary chunks
+fn parse_glb(bytes: &[u8]) -> Result<(Value, Option<Vec<u8>>)> {
+    let read_u32 = |offset: usize| -> Result<u32> {
+        let word = bytes
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("GLB file is truncated"))?;
+        Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+    };
+    let version = read_u32(4)?;
+    if version != 2 {
+        return Err(anyhow!("Unsupported GLB version {}", version));
+    }
+    let length = (read_u32(8)? as usize).min(bytes.len());
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = GLB_HEADER_LENGTH;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(offset)? as usize;
+        let chunk_type = read_u32(offset + 4)?;
+        let end = (offset + 8)
+            .checked_add(chunk_length)
+            .ok_or_else(|| anyhow!("GLB chunk length {} is too large", chunk_length))?;
+        let data = bytes
+            .get(offset + 8..end)
+            .ok_or_else(|| anyhow!("GLB chunk runs past the end of the file"))?;
+        match chunk_type {
+            CHUNK_JSON => json = Some(serde_json::from_slice(data)?),
+            CHUNK_BIN if bin.is_none() => bin = Some(data.to_vec()),
+            // Unknown chunks must be ignored
+            _ => {}
+        }
+        offset = end;
+    }
+    let json = json.ok_or_else(|| anyhow!("GLB file has no JSON chunk"))?;
+    Ok((json, bin))
+}
+
+struct Document {
+    json: Value,
+    buffers: Vec<Vec<u8>>,
+    base_dir: PathBuf,
+}
+
+impl Document {
+    fn new(json: Value, bin: Option<Vec<u8>>, base_dir: &Path) -> Result<Self> {
+        let mut bin = bin;
+        let buffers = get_array(&json, "buffers")
+            .iter()
+            .enumerate()
+            .map(
+                |(index, buffer)| match buffer.get("uri").and_then(Value::as_str) {
+                    Some(uri) => load_uri(uri, base_dir),
+                    // Only the first buffer can point at the GLB binary chunk
+                    None if index == 0 => bin.take().ok_or_else(|| {
+                        anyhow!("Buffer 0 has no uri and there is no GLB binary chunk")
+                    }),
+                    None => Err(anyhow!("Buffer {} has no uri", index)),
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Document {
+            json,
+            buffers,
+            base_dir: base_dir.to_path_buf(),
+        })
+    }
+
+    fn item(&self, kind: &str, index: usize) -> Result<&Value> {
+        get_array(&self.json, kind)
+            .get(index)
+            .ok_or_else(|| anyhow!("{} index {} out of range", kind, index))
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>)> {
+        let view = self.item("bufferViews", index)?;
+        let buffer = get_usize(view, "buffer")
+            .and_then(|buffer| self.buffers.get(buffer))
+            .ok_or_else(|| anyhow!("Buffer view {} has no buffer", index))?;
+        let offset = get_usize(view, "byteOffset").unwrap_or(0);
+        let length = get_usize(view, "byteLength")
+            .ok_or_else(|| anyhow!("Buffer view {} has no byteLength", index))?;
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| buffer.get(offset..end))
+            .ok_or_else(|| anyhow!("Buffer view {} runs past the end of its buffer", index))?;
+        Ok((data, get_usize(view, "byteStride")))
+    }
+
+    // Every element of an accessor, normalized integers mapped to 0..1 or -1..1
+    fn accessor(&self, index: usize) -> Result<Vec<Vec<f64>>> {
+        let accessor = self.item("accessors", index)?;
+        let count = get_usize(accessor, "count")
+            .ok_or_else(|| anyhow!("Accessor {} has no count", index))?;
+        let component_type = ComponentType::new(
+            accessor
+                .get("componentType")
+                .and_then(Value::as_u64)
+                .unwrap_or(0),
+        )?;
+        let components = match accessor.get("type").and_then(Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            other => return Err(anyhow!("Accessor {} has unknown type {:?}", index, other)),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let layout = Layout {
+            component_type,
+            components,
+            normalized,
+        };
+
+        // No buffer view means all zeros, usually with sparse values on top
+        let mut elements = match get_usize(accessor, "bufferView") {
+            Some(view) => {
+                let (data, stride) = self.buffer_view(view)?;
+                let offset = get_usize(accessor, "byteOffset").unwrap_or(0);
+                layout.read(data, offset, stride, count)?
+            }
+            // Nothing in the file backs these, so don't trust a count bigger than
+            // all of its buffers before allocating
+            None => {
+                let limit: usize = self.buffers.iter().map(Vec::len).sum();
+                if count > limit {
+                    return Err(anyhow!(
+                        "Accessor {} count {} is larger than the file's buffers",
+                        index,
+                        count
+                    ));
+                }
+                vec![vec![0.; components]; count]
+            }
+        };
+
+        if let Some(sparse) = accessor.get("sparse") {
+            let sparse_count = get_usize(sparse, "count").unwrap_or(0);
+            if sparse_count > count {
+                return Err(anyhow!(
+                    "Sparse accessor {} replaces {} of {} elements",
+                    index,
+                    sparse_count,
+                    count
+                ));
+            }
+            let indices = sparse
+                .get("indices")
+                .ok_or_else(|| anyhow!("Sparse accessor {} has no indices", index))?;
+            let values = sparse
+                .get("values")
+                .ok_or_else(|| anyhow!("Sparse accessor {} has no values", index))?;
+            let index_layout = Layout {
+                component_type: ComponentType::new(
+                    indices
+                        .get("componentType")
+                        .and_then(Value::as_u64)
+                        .unwrap_or(0),
+                )?,
+                components: 1,
+                normalized: false,
+            };
+            let read_sparse = |part: &Value, layout: &Layout| -> Result<Vec<Vec<f64>>> {
+                let view = get_usize(part, "bufferView")
+                    .ok_or_else(|| anyhow!("Sparse accessor {} has no bufferView", index))?;
+                let (data, _) = self.buffer_view(view)?;
+                let offset = get_usize(part, "byteOffset").unwrap_or(0);
+                layout.read(data, offset, None, sparse_count)
+            };
+            let targets = read_sparse(indices, &index_layout)?;
+            let replacements = read_sparse(values, &layout)?;
+            for (target, replacement) in targets.into_iter().zip(replacements) {
+                let element = elements
+                    .get_mut(target[0] as usize)
+                    .ok_or_else(|| anyhow!("Sparse accessor {} index out of range", index))?;
+                *element = replacement;
+            }
+        }
+        Ok(elements)
+    }
+
+    // PNG and JPEG can't be decoded yet, those textures only carry their path
+    fn texture(&self, info: Option<&Value>) -> Option<Texture> {
+        let texture = self.item("textures", get_usize(info?, "index")?).ok()?;
+        let source = get_usize(texture, "source")?;
+        let image = self.item("images", source).ok()?;
+        if let Some(uri) = image.get("uri").and_then(Value::as_str)
+            && !uri.starts_with("data:")
+        {
+            return Some(Texture::load(self.base_dir.join(decode_uri(uri))));
+        }
+        let name = image
+            .get("name")
+            .and_then(Value::as_str)
+            .map_or_else(|| format!("image_{}", source), str::to_string);
+        let is_tga = image
+            .get("mimeType")
+            .and_then(Value::as_str)
+            .is_some_and(|mime_type| mime_type.ends_with("tga"));
+        let bytes = match (
+            image.get("uri").and_then(Value::as_str),
+            get_usize(image, "bufferView"),
+        ) {
+            (Some(uri), _) => load_uri(uri, &self.base_dir).ok(),
+            (None, Some(view)) => self.buffer_view(view).ok().map(|(data, _)| data.to_vec()),
+            (None, None) => None,
+        };
+        let image = bytes
+            .filter(|_| is_tga)
+            .and_then(|bytes| Image::<RGBA>::read_from_bytes(&bytes).ok());
+        Some(Texture {
+            path: PathBuf::from(name),
+            image,
+        })
+    }
+
+    fn materials(&self) -> Vec<Material> {
+        get_array(&self.json, "materials")
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let name = value
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| format!("material_{}", index), str::to_string);
+                let mut material = Material::new(&name);
+                // glTF defaults, which differ from OBJ's
+                material.metallic = 1.;
+                material.roughness = 1.;
+                if let Some(pbr) = value.get("pbrMetallicRoughness") {
+                    if let Some([r, g, b, a]) = get_floats::<4>(pbr, "baseColorFactor") {
+                        material.diffuse = Vector3::new([r, g, b]);
+                        material.dissolve = a;
+                    }
+                    if let Some(metallic) = pbr.get("metallicFactor").and_then(Value::as_f64) {
+                        material.metallic = metallic;
+                    }
+                    if let Some(roughness) = pbr.get("roughnessFactor").and_then(Value::as_f64) {
+                        material.roughness = roughness;
+                    }
+                    material.diffuse_map = self.texture(pbr.get("baseColorTexture"));
+                    material.metallic_roughness_map =
+                        self.texture(pbr.get("metallicRoughnessTexture"));
+                }
+                material.bump_map = self.texture(value.get("normalTexture"));
+                material
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ComponentType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    UInt32,
+    Float32,
+}
+
+impl ComponentType {
+    fn new(code: u64) -> Result<Self> {
+        match code {
+            5120 => Ok(ComponentType::Int8),
+            5121 => Ok(ComponentType::UInt8),
+            5122 => Ok(ComponentType::Int16),
+            5123 => Ok(ComponentType::UInt16),
+            5125 => Ok(ComponentType::UInt32),
+            5126 => Ok(ComponentType::Float32),
+            _ => Err(anyhow!("Unknown accessor component type {}", code)),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ComponentType::Int8 | ComponentType::UInt8 => 1,
+            ComponentType::Int16 | ComponentType::UInt16 => 2,
+            ComponentType::UInt32 | ComponentType::Float32 => 4,
+        }
+    }
+
+    fn read(&self, bytes: &[u8], normalized: bool) -> f64 {
+        let value = match self {
+            ComponentType::Int8 => bytes[0] as i8 as f64,
+            ComponentType::UInt8 => bytes[0] as f64,
+            ComponentType::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ComponentType::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ComponentType::UInt32 => {
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            ComponentType::Float32 => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+        };
+        if !normalized {
+            return value;
+        }
+        match self {
+            ComponentType::Int8 => (value / 127.).max(-1.),
+            ComponentType::UInt8 => value / 255.,
+            ComponentType::Int16 => (value / 32767.).max(-1.),
+            ComponentType::UInt16 => value / 65535.,
+            ComponentType::UInt32 => value / u32::MAX as f64,
+            ComponentType::Float32 => value,
+        }
+    }
+}
+
+struct Layout {
+    component_type: ComponentType,
+    components: usize,
+    normalized: bool,
+}
+
+impl Layout {
+    fn read(
+        &self,
+        data: &[u8],
+        offset: usize,
+        stride: Option<usize>,
+        count: usize,
+    ) -> Result<Vec<Vec<f64>>> {
+        let size = self.component_type.size();
+        let element_size = size * self.components;
+        let stride = stride.unwrap_or(element_size);
+        (0..count)
+            .map(|element| {
+                let bytes = element
+                    .checked_mul(stride)
+                    .and_then(|start| start.checked_add(offset))
+                    .and_then(|start| Some(start..start.checked_add(element_size)?))
+                    .and_then(|range| data.get(range))
+                    .ok_or_else(|| anyhow!("Accessor runs past the end of its buffer view"))?;
+                Ok(bytes
+                    .chunks_exact(size)
+                    .map(|component| self.component_type.read(component, self.normalized))
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+type Transform = Matrix<f64, 4, 4>;
+
+fn identity() -> Transform {
+    Matrix::new(std::array::from_fn(|i| {
+        Vector::new(std::array::from_fn(|j| if i == j { 1. } else { 0. }))
+    }))
+}
+
+// Either "matrix" (column major) or translation * rotation * scale
+fn node_transform(node: &Value) -> Transform {
+    if let Some(m) = get_floats::<16>(node, "matrix") {
+        return Matrix::new(std::array::from_fn(|row| {
+            Vector::new(std::array::from_fn(|column| m[column * 4 + row]))
+        }));
+    }
+    let [tx, ty, tz] = get_floats::<3>(node, "translation").unwrap_or([0.; 3]);
+    let [x, y, z, w] = get_floats::<4>(node, "rotation").unwrap_or([0., 0., 0., 1.]);
+    let [sx, sy, sz] = get_floats::<3>(node, "scale").unwrap_or([1.; 3]);
+    let rotation = [
+        [
+            1. - 2. * (y * y + z * z),
+            2. * (x * y - z * w),
+            2. * (x * z + y * w),
+        ],
+        [
+            2. * (x * y + z * w),
+            1. - 2. * (x * x + z * z),
+            2. * (y * z - x * w),
+        ],
+        [
+            2. * (x * z - y * w),
+            2. * (y * z + x * w),
+            1. - 2. * (x * x + y * y),
+        ],
+    ];
+    let scale = [sx, sy, sz];
+    let translation = [tx, ty, tz];
+    Matrix::new(std::array::from_fn(|row| {
+        Vector::new(std::array::from_fn(|column| match (row, column) {
+            (3, 3) => 1.,
+            (3, _) => 0.,
+            (_, 3) => translation[row],
+            _ => rotation[row][column] * scale[column],
+        }))
+    }))
+}
+
+fn transform_point(transform: &Transform, point: &[f64]) -> Vector3<f64> {
+    let column = Matrix::new([Vector::new([point[0], point[1], point[2], 1.])]).transpose();
+    let [x, y, z, _] = (transform.clone() * column).to_vector().get_data();
+    Vector3::new([x, y, z])
+}
+
+fn linear_columns(transform: &Transform) -> [Vector3<f64>; 3] {
+    std::array::from_fn(|j| Vector3::new([transform[0][j], transform[1][j], transform[2][j]]))
+}
+
+// Negative when the transform mirrors, which flips the winding of every triangle
+fn determinant(transform: &Transform) -> f64 {
+    let [a, b, c] = linear_columns(transform);
+    a.dot(&b.cross(&c))
+}
+
+// Normals go through the inverse transpose, built here from the cofactors
+fn normal_transform(transform: &Transform) -> [Vector3<f64>; 3] {
+    let [a, b, c] = linear_columns(transform);
+    let sign = if determinant(transform) < 0. { -1. } else { 1. };
+    [b.cross(&c) * sign, c.cross(&a) * sign, a.cross(&b) * sign]
+}
+
+fn transform_normal(columns: &[Vector3<f64>; 3], normal: &[f64]) -> Vector3<f64> {
+    let transformed = columns[0].clone() * normal[0]
+        + columns[1].clone() * normal[1]
+        + columns[2].clone() * normal[2];
+    transformed.normalize()
+}
+
+struct SceneBuilder<'a> {
+    document: &'a Document,
+    obj: ObjFile,
+}
+
+impl SceneBuilder<'_> {
+    fn node(
+        &mut self,
+        index: usize,
+        parent: &Transform,
+        visited: &mut HashSet<usize>,
+    ) -> Result<()> {
+        if !visited.insert(index) {
+            return Err(anyhow!("Node {} is reachable more than once", index));
+        }
+        let node = self.document.item("nodes", index)?;
+        let transform = parent.clone() * node_transform(node);
+        if let Some(mesh) = get_usize(node, "mesh") {
+            let first_face = self.obj.faces.len();
+            self.mesh(mesh, &transform)?;
+            let mesh_value = self.document.item("meshes", mesh)?;
+            let name = [node, mesh_value]
+                .iter()
+                .find_map(|value| value.get("name").and_then(Value::as_str))
+                .map_or_else(|| format!("mesh_{}", mesh), str::to_string);
+            self.obj.objects.push(SubMesh {
+                name,
+                faces: first_face..self.obj.faces.len(),
+            });
+        }
+        for child in get_array(node, "children") {
+            let child = child
+                .as_u64()
+                .ok_or_else(|| anyhow!("Node {} has an invalid child", index))?;
+            self.node(child as usize, &transform, visited)?;
+        }
+        Ok(())
+    }
+
+    fn mesh(&mut self, index: usize, transform: &Transform) -> Result<()> {
+        let mesh = self.document.item("meshes", index)?;
+        let normal_columns = normal_transform(transform);
+        for primitive in get_array(mesh, "primitives") {
+            self.primitive(primitive, transform, &normal_columns)?;
+        }
+        Ok(())
+    }
+
+    fn primitive(
+        &mut self,
+        primitive: &Value,
+        transform: &Transform,
+        normal_columns: &[Vector3<f64>; 3],
+    ) -> Result<()> {
+        let attributes = primitive
+            .get("attributes")
+            .ok_or_else(|| anyhow!("Primitive has no attributes"))?;
+        let attribute = |name: &str| -> Result<Option<Vec<Vec<f64>>>> {
+            get_usize(attributes, name)
+                .map(|accessor| self.document.accessor(accessor))
+                .transpose()
+        };
+        let positions =
+            attribute("POSITION")?.ok_or_else(|| anyhow!("Primitive has no POSITION attribute"))?;
+        let normals = attribute("NORMAL")?;
+        let texture_coords = attribute("TEXCOORD_0")?;
+        let colors = attribute("COLOR_0")?;
+        let count = positions.len();
+        for (name, values) in [
+            ("NORMAL", &normals),
+            ("TEXCOORD_0", &texture_coords),
+            ("COLOR_0", &colors),
+        ] {
+            if values.as_ref().is_some_and(|values| values.len() != count) {
+                return Err(anyhow!("{} has a different count than POSITION", name));
+            }
+        }
+
+        let vertex_base = self.obj.verticies.len();
+        let normal_base = self.obj.normals.len();
+        let texture_base = self.obj.texture_coords.len();
+        for (index, position) in positions.iter().enumerate() {
+            let color = colors
+                .as_ref()
+                .map(|colors| Vector3::new([colors[index][0], colors[index][1], colors[index][2]]));
+            self.obj
+                .push_vertex(transform_point(transform, position), color);
+        }
+        if let Some(normals) = &normals {
+            for normal in normals {
+                self.obj
+                    .normals
+                    .push(transform_normal(normal_columns, normal));
+            }
+        }
+        if let Some(texture_coords) = &texture_coords {
+            // glTF puts v = 0 at the top of the image, OBJ at the bottom
+            for uv in texture_coords {
+                self.obj
+                    .texture_coords
+                    .push(Vector3::new([uv[0], 1. - uv[1], 0.]));
+            }
+        }
+
+        let indices: Vec<usize> = match get_usize(primitive, "indices") {
+            Some(accessor) => self
+                .document
+                .accessor(accessor)?
+                .into_iter()
+                .map(|index| index[0] as usize)
+                .collect(),
+            None => (0..count).collect(),
+        };
+        if let Some(index) = indices.iter().find(|&&index| index >= count) {
+            return Err(anyhow!("Index {} out of range, {} verticies", index, count));
+        }
+        let corner = |index: usize| FaceVertex {
+            vertex: vertex_base + index,
+            texture: texture_coords.as_ref().map(|_| texture_base + index),
+            normal: normals.as_ref().map(|_| normal_base + index),
+            tangent: None,
+        };
+
+        let first_face = self.obj.faces.len();
+        let mode = primitive
+            .get("mode")
+            .and_then(Value::as_u64)
+            .unwrap_or(TRIANGLES);
+        let mut triangles: Vec<[usize; 3]> = match mode {
+            TRIANGLES => indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect(),
+            // Every other strip triangle is flipped to keep the winding
+            TRIANGLE_STRIP => indices
+                .windows(3)
+                .enumerate()
+                .map(|(i, w)| {
+                    if i % 2 == 0 {
+                        [w[0], w[1], w[2]]
+                    } else {
+                        [w[1], w[0], w[2]]
+                    }
+                })
+                .collect(),
+            TRIANGLE_FAN => indices
+                .windows(2)
+                .skip(1)
+                .map(|w| [indices[0], w[0], w[1]])
+                .collect(),
+            _ => Vec::new(),
+        };
+        // Mirrored nodes would turn the faces inside out, keep them counter clockwise
+        if determinant(transform) < 0. {
+            triangles.iter_mut().for_each(|triangle| triangle.reverse());
+        }
+        for triangle in triangles {
+            self.obj.faces.push(Face {
+                verticies: triangle.map(corner).to_vec(),
+            });
+        }
+        let mut polyline = |indices: &[usize]| {
+            self.obj.polylines.push(Polyline {
+                verticies: indices.iter().map(|&index| corner(index)).collect(),
+            })
+        };
+        match mode {
+            POINTS => self
+                .obj
+                .points
+                .extend(indices.iter().map(|index| vertex_base + index)),
+            LINES => indices.chunks_exact(2).for_each(&mut polyline),
+            LINE_STRIP if indices.len() >= 2 => polyline(&indices),
+            LINE_LOOP if indices.len() >= 2 => {
+                polyline(&[indices.as_slice(), &indices[..1]].concat())
+            }
+            TRIANGLES | TRIANGLE_STRIP | TRIANGLE_FAN | LINE_STRIP | LINE_LOOP => {}
+            _ => return Err(anyhow!("Unknown primitive mode {}", mode)),
+        }
+
+        if let Some(material) = get_usize(primitive, "material") {
+            if material >= self.obj.materials.len() {
+                return Err(anyhow!("Material index {} out of range", material));
+            }
+            self.obj.material_ranges.push(MaterialRange {
+                material,
+                faces: first_face..self.obj.faces.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// Root nodes of the default scene, or every node without a parent when there are no scenes
+fn root_nodes(json: &Value) -> Result<Vec<usize>> {
+    let scenes = get_array(json, "scenes");
+    if !scenes.is_empty() {
+        let scene = get_usize(json, "scene").unwrap_or(0);
+        let scene = scenes
+            .get(scene)
+            .ok_or_else(|| anyhow!("Scene {} out of range", scene))?;
+        return Ok(get_array(scene, "nodes")
+            .iter()
+            .filter_map(|node| node.as_u64().map(|node| node as usize))
+            .collect());
+    }
+    let nodes = get_array(json, "nodes");
+    let children: HashSet<usize> = nodes
+        .iter()
+        .flat_map(|node| get_array(node, "children"))
+        .filter_map(|child| child.as_u64().map(|child| child as usize))
+        .collect();
+    Ok((0..nodes.len())
+        .filter(|node| !children.contains(node))
+        .collect())
+}
+
+// `base_dir` is where external buffers and images are looked up
+pub fn parse_gltf_bytes(bytes: &[u8], base_dir: &Path) -> Result<ObjFile> {
+    let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+        parse_glb(bytes)?
+    } else {
+        (serde_json::from_slice(bytes)?, None)
+    };
+    let version = json
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    if !version.starts_with("2.") {
+        return Err(anyhow!("Unsupported glTF version \"{}\"", version));
+    }
+
+    let document = Document::new(json, bin, base_dir)?;
+    let mut builder = SceneBuilder {
+        document: &document,
+        obj: ObjFile {
+            materials: document.materials(),
+            ..Default::default()
+        },
+    };
+    let mut visited = HashSet::new();
+    for node in root_nodes(&document.json)? {
+        builder.node(node, &identity(), &mut visited)?;
+    }
+    let mut obj = builder.obj;
+    obj.triangulate();
+    Ok(obj)
+}
+
+// Handles both .gltf and .glb
+pub fn parse_gltf_file(path: &Path) -> Result<ObjFile> {
+    let bytes = std::fs::read(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    parse_gltf_bytes(&bytes, base_dir)
+        .map_err(|e| anyhow!("Failed to read glTF file {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        gltf::{decode_base64, parse_gltf_bytes},
+        math::Vector3,
+    };
+
+    // One triangle with uvs, u16 indices and a translated node
+    const TRIANGLE: &str = r#"{
+        "asset": {"version": "2.0"},
+        "scene": 0,
+        "scenes": [{"nodes": [0]}],
+        "nodes": [{"name": "parent", "translation": [0, 0, 2], "children": [1]},
+                  {"mesh": 0, "scale": [2, 2, 2]}],
+        "meshes": [{"name": "tri", "primitives": [{
+            "attributes": {"POSITION": 0, "TEXCOORD_0": 1},
+            "indices": 2,
+            "material": 0
+        }]}],
+        "materials": [{"name": "gold", "pbrMetallicRoughness": {
+            "baseColorFactor": [1, 0.8, 0.2, 0.5], "metallicFactor": 0.9, "roughnessFactor": 0.3,
+            "baseColorTexture": {"index": 0}
+        }}],
+        "textures": [{"source": 0}],
+        "images": [{"uri": "textures/gold%20leaf.png"}],
+        "buffers": [{"byteLength": 68, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAIA/AAABAAIAAAA="}],
+        "bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": 36},
+                        {"buffer": 0, "byteOffset": 36, "byteLength": 24},
+                        {"buffer": 0, "byteOffset": 60, "byteLength": 6}],
+        "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+                      {"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"},
+                      {"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}]
+    }"#;
+
+    fn float_bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut bytes = b"glTF".to_vec();
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend((length as u32).to_le_bytes());
+        bytes.extend((json.len() as u32).to_le_bytes());
+        bytes.extend(b"JSON");
+        bytes.extend(json);
+        bytes.extend((bin.len() as u32).to_le_bytes());
+        bytes.extend(b"BIN\0");
+        bytes.extend(bin);
+        bytes
+    }
+
+    #[test]
+    fn gltf_base64() {
+        assert_eq!(b"hello".to_vec(), decode_base64("aGVsbG8=").unwrap());
+        assert_eq!(b"hi!".to_vec(), decode_base64("aGkh").unwrap());
+        assert!(decode_base64("a$==").is_err());
+    }
+
+    #[test]
+    fn gltf_embedded_buffer() {
+        let obj = parse_gltf_bytes(TRIANGLE.as_bytes(), Path::new("assets")).unwrap();
+        assert_eq!(
+            vec![
+                Vector3::new([0., 0., 2.]),
+                Vector3::new([2., 0., 2.]),
+                Vector3::new([0., 2., 2.])
+            ],
+            obj.verticies
+        );
+        // v is flipped
+        assert_eq!(Vector3::new([0., 1., 0.]), obj.texture_coords[0]);
+        assert_eq!(Vector3::new([0., 0., 0.]), obj.texture_coords[2]);
+        assert_eq!(1, obj.faces.len());
+        assert_eq!(Some(2), obj.faces[0].verticies[2].texture);
+        assert_eq!(1, obj.triangles.len());
+        assert_eq!("tri", obj.objects[0].name);
+        assert_eq!(0..1, obj.material_ranges[0].faces);
+
+        let gold = &obj.materials[0];
+        assert_eq!("gold", gold.name);
+        assert_eq!(Vector3::new([1., 0.8, 0.2]), gold.diffuse);
+        assert_eq!(
+            (0.5, 0.9, 0.3),
+            (gold.dissolve, gold.metallic, gold.roughness)
+        );
+        assert_eq!(
+            Path::new("assets/textures/gold leaf.png"),
+            gold.diffuse_map.as_ref().unwrap().path
+        );
+    }
+
+    #[test]
+    fn gltf_binary_strip_and_lines() {
+        // A unit square as a strip, rotated 90 degrees around z, normals and colors
+        let mut bin = float_bytes(&[0., 0., 0., 1., 0., 0., 0., 1., 0., 1., 1., 0.]);
+        bin.extend(float_bytes(&[0., 0., 1.].repeat(4)));
+        bin.extend([
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+        ]);
+        bin.extend([0, 1, 3, 2]);
+        let json = r#"{
+            "asset": {"version": "2.0"},
+            "nodes": [{"mesh": 0, "rotation": [0, 0, 0.7071067811865476, 0.7071067811865476]}],
+            "meshes": [{"primitives": [
+                {"attributes": {"POSITION": 0, "NORMAL": 1, "COLOR_0": 2}, "mode": 5},
+                {"attributes": {"POSITION": 0}, "indices": 3, "mode": 2},
+                {"attributes": {"POSITION": 0}, "mode": 0}
+            ]}],
+            "buffers": [{"byteLength": 116}],
+            "bufferViews": [{"buffer": 0, "byteLength": 48},
+                            {"buffer": 0, "byteOffset": 48, "byteLength": 48},
+                            {"buffer": 0, "byteOffset": 96, "byteLength": 16},
+                            {"buffer": 0, "byteOffset": 112, "byteLength": 4}],
+            "accessors": [{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"},
+                          {"bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3"},
+                          {"bufferView": 2, "componentType": 5121, "normalized": true, "count": 4, "type": "VEC4"},
+                          {"bufferView": 3, "componentType": 5121, "count": 4, "type": "SCALAR"}]
+        }"#;
+        let obj = parse_gltf_bytes(&glb(json, &bin), Path::new("")).unwrap();
+
+        assert_eq!(12, obj.verticies.len());
+        let [x, y, z] = obj.verticies[1].get_data();
+        assert!(x.abs() < 1e-6 && (y - 1.).abs() < 1e-6 && z == 0.);
+        assert_eq!(Vector3::new([0., 0., 1.]), obj.normals[0]);
+        assert_eq!(Vector3::new([1., 0., 0.]), obj.vertex_colors[0]);
+        // Verticies of the later primitives have no color
+        assert_eq!(Vector3::new([1., 1., 1.]), obj.vertex_colors[4]);
+
+        let faces: Vec<Vec<usize>> = obj
+            .faces
+            .iter()
+            .map(|face| face.verticies.iter().map(|corner| corner.vertex).collect())
+            .collect();
+        assert_eq!(vec![vec![0, 1, 2], vec![2, 1, 3]], faces);
+        let line: Vec<usize> = obj.polylines[0]
+            .verticies
+            .iter()
+            .map(|corner| corner.vertex)
+            .collect();
+        assert_eq!(vec![4, 5, 7, 6, 4], line);
+        assert_eq!(vec![8, 9, 10, 11], obj.points);
+        assert_eq!("mesh_0", obj.objects[0].name);
+    }
+
+    #[test]
+    fn gltf_invalid() {
+        let old_version = TRIANGLE.replace("\"2.0\"", "\"1.0\"");
+        assert!(parse_gltf_bytes(old_version.as_bytes(), Path::new("")).is_err());
+        let bad_index = TRIANGLE.replace(
+            "\"count\": 3, \"type\": \"SCALAR\"",
+            "\"count\": 4, \"type\": \"SCALAR\"",
+        );
+        assert!(parse_gltf_bytes(bad_index.as_bytes(), Path::new("")).is_err());
+        let missing_buffer = TRIANGLE.replace(
+            "\"buffer\": 0, \"byteOffset\": 60",
+            "\"buffer\": 1, \"byteOffset\": 60",
+        );
+        assert!(parse_gltf_bytes(missing_buffer.as_bytes(), Path::new("")).is_err());
+        assert!(parse_gltf_bytes(b"glTF\x02\x00\x00\x00", Path::new("")).is_err());
+        let huge_offset =
+            TRIANGLE.replace("\"byteOffset\": 60", "\"byteOffset\": 18446744073709551615");
+        assert!(parse_gltf_bytes(huge_offset.as_bytes(), Path::new("")).is_err());
+        let huge_count = TRIANGLE.replace(
+            "{\"bufferView\": 1, \"componentType\": 5126, \"count\": 3,",
+            "{\"componentType\": 5126, \"count\": 4000000000,",
+        );
+        let err = parse_gltf_bytes(huge_count.as_bytes(), Path::new(""))
+            .err()
+            .unwrap();
+        assert!(format!("{:#}", err).contains("larger than the file's buffers"));
+        let huge_sparse = TRIANGLE.replace(
+            "\"count\": 3, \"type\": \"VEC2\"",
+            "\"count\": 3, \"type\": \"VEC2\", \"sparse\": {\"count\": 4000000000, \"indices\": {\"bufferView\": 2, \"componentType\": 5123}, \"values\": {\"bufferView\": 1}}",
+        );
+        let err = parse_gltf_bytes(huge_sparse.as_bytes(), Path::new(""))
+            .err()
+            .unwrap();
+        assert!(format!("{:#}", err).contains("replaces 4000000000 of 3"));
+    }
+
+    #[test]
+    fn gltf_mirrored_node() {
+        let mirrored = TRIANGLE.replace("\"scale\": [2, 2, 2]", "\"scale\": [-2, 2, 2]");
+        let obj = parse_gltf_bytes(mirrored.as_bytes(), Path::new("")).unwrap();
+        assert_eq!(Vector3::new([-2., 0., 2.]), obj.verticies[1]);
+        let face: Vec<usize> = obj.faces[0]
+            .verticies
+            .iter()
+            .map(|corner| corner.vertex)
+            .collect();
+        assert_eq!(vec![2, 1, 0], face);
+    }
+}
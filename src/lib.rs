@@ -1,7 +1,12 @@
 pub mod colors;
 pub mod draw;
+pub mod gltf;
 pub mod math;
+pub mod mesh;
 pub mod obj;
+pub mod off;
+pub mod ply;
+pub mod stl;
 pub mod tga;
 pub mod triangle;
 pub mod types;
@@ -1,12 +1,24 @@
 use std::{path::Path, time::Instant};
 
+use anyhow::{Result, anyhow};
 use tiny_renderer::{
-    draw::draw_obj_file,
-    obj::parse_obj_file,
+    draw::{DrawOptions, draw_obj_file_with_options},
+    gltf::parse_gltf_file,
+    mesh::analyze,
+    obj::{ObjFile, parse_obj_file},
+    off::parse_off_file,
+    ply::parse_ply_file,
+    stl::parse_stl_file,
     tga::{Image, RGB},
 };
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("stats") {
+        print_stats(&args[1..]);
+        return;
+    }
+
     let start = Instant::now();
     test_obj_files();
     let end = Instant::now();
@@ -26,7 +38,11 @@ fn test_obj_files() {
     let mut img = Image::<RGB>::new(width, height);
 
     if let Ok(model) = parse_obj_file(path) {
-        let draw_res = draw_obj_file(model, &mut img);
+        let options = DrawOptions {
+            dump_z_buffer: cfg!(debug_assertions),
+            ..Default::default()
+        };
+        let draw_res = draw_obj_file_with_options(model, &mut img, &options);
 
         match draw_res {
             Ok(_) => {
@@ -38,3 +54,40 @@ fn test_obj_files() {
         };
     };
 }
+
+fn load_mesh(path: &Path) -> Result<ObjFile> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("obj") => parse_obj_file(path),
+        Some("ply") => parse_ply_file(path),
+        Some("stl") => parse_stl_file(path),
+        Some("off") => parse_off_file(path),
+        Some("gltf" | "glb") => parse_gltf_file(path),
+        _ => Err(anyhow!("Unknown mesh format {}", path.display())),
+    }
+}
+
+// "stats <mesh>..." prints the analysis report of every mesh
+// Exits with 1 when there's nothing to report on or any mesh fails to load
+fn print_stats(paths: &[String]) {
+    if paths.is_empty() {
+        eprintln!("Usage: tiny_renderer stats <mesh>...");
+        std::process::exit(1);
+    }
+    let mut failed = false;
+    for path in paths {
+        match load_mesh(Path::new(path)) {
+            Ok(mesh) => println!("{}\n{}", path, analyze(&mesh)),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
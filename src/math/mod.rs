@@ -3,5 +3,6 @@ pub mod vector;
 
 pub use vector::Vector;
 pub use vector::Vector3;
+pub use vector::Vector4;
 
 pub use matrix::Matrix;
@@ -1,5 +1,7 @@
 pub mod vector3;
+pub mod vector4;
 pub mod vector_base;
 
 pub use vector_base::Vector;
 pub use vector3::Vector3;
+pub use vector4::Vector4;
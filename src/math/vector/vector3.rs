@@ -20,4 +20,36 @@ where
     pub fn z(&self) -> T {
         self.get_data()[2]
     }
+
+    pub fn cross(&self, other: &Self) -> Self {
+        Vector3::new([
+            self.y() * other.z() - self.z() * other.y(),
+            self.z() * other.x() - self.x() * other.z(),
+            self.x() * other.y() - self.y() * other.x(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::Vector3;
+
+    #[test]
+    fn vec3_cross_int() {
+        let vec_one = Vector3::new([1, 2, 3]);
+        let vec_two = Vector3::new([4, 5, 6]);
+
+        let res = vec_one.cross(&vec_two);
+        assert_eq!(Vector3::new([-3, 6, -3]), res);
+        assert_eq!(0, res.dot(&vec_one));
+    }
+
+    #[test]
+    fn vec3_cross_float() {
+        let x = Vector3::new([1., 0., 0.]);
+        let y = Vector3::new([0., 1., 0.]);
+
+        assert_eq!(Vector3::new([0., 0., 1.]), x.cross(&y));
+        assert_eq!(Vector3::new([0., 0., -1.]), y.cross(&x));
+    }
 }
@@ -0,0 +1,31 @@
+use num::Num;
+
+use crate::math::{Vector, Vector3};
+use std::{fmt::Display, iter::Sum};
+
+pub type Vector4<T> = Vector<T, 4>;
+
+impl<T> Vector4<T>
+where
+    T: Num + Sum + Copy + Display,
+{
+    pub fn x(&self) -> T {
+        self.get_data()[0]
+    }
+
+    pub fn y(&self) -> T {
+        self.get_data()[1]
+    }
+
+    pub fn z(&self) -> T {
+        self.get_data()[2]
+    }
+
+    pub fn w(&self) -> T {
+        self.get_data()[3]
+    }
+
+    pub fn xyz(&self) -> Vector3<T> {
+        Vector3::new([self.x(), self.y(), self.z()])
+    }
+}
@@ -1,10 +1,10 @@
 use std::{
     fmt::{Debug, Display},
     iter::Sum,
-    ops::{Add, Div, Index, IndexMut, Mul, Sub},
+    ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use num::Num;
+use num::{Float, Num, Signed};
 
 #[derive(Debug, Clone)]
 pub struct Vector<T, const N: usize>
@@ -30,6 +30,102 @@ where
     pub fn get_data(&self) -> [T; N] {
         self.data
     }
+
+    pub fn norm_squared(&self) -> T {
+        self.dot(self)
+    }
+
+    // `self` at t = 0, `other` at t = 1
+    pub fn lerp(&self, other: &Self, t: T) -> Self {
+        Vector {
+            data: std::array::from_fn(|i| self.data[i] + (other.data[i] - self.data[i]) * t),
+        }
+    }
+
+    // Mirrors `self` about the plane with unit `normal`
+    pub fn reflect(&self, normal: &Self) -> Self {
+        let scale = (T::one() + T::one()) * self.dot(normal);
+        Vector {
+            data: std::array::from_fn(|i| self.data[i] - normal.data[i] * scale),
+        }
+    }
+}
+
+// Component-wise min and max
+impl<T, const N: usize> Vector<T, N>
+where
+    T: Num + Sum + Copy + Display + PartialOrd,
+{
+    pub fn min(&self, other: &Self) -> Self {
+        Vector {
+            data: std::array::from_fn(|i| {
+                if other.data[i] < self.data[i] {
+                    other.data[i]
+                } else {
+                    self.data[i]
+                }
+            }),
+        }
+    }
+
+    pub fn max(&self, other: &Self) -> Self {
+        Vector {
+            data: std::array::from_fn(|i| {
+                if other.data[i] > self.data[i] {
+                    other.data[i]
+                } else {
+                    self.data[i]
+                }
+            }),
+        }
+    }
+}
+
+impl<T, const N: usize> Vector<T, N>
+where
+    T: Num + Sum + Copy + Display + Signed,
+{
+    pub fn abs(&self) -> Self {
+        Vector {
+            data: self.data.map(|element| element.abs()),
+        }
+    }
+}
+
+// Float impl block
+impl<T, const N: usize> Vector<T, N>
+where
+    T: Num + Sum + Copy + Display + Float,
+{
+    pub fn norm(&self) -> T {
+        self.norm_squared().sqrt()
+    }
+
+    // Zero vectors stay zero
+    pub fn normalize(&self) -> Self {
+        let norm = self.norm();
+        if norm == T::zero() {
+            return self.clone();
+        }
+        Vector {
+            data: self.data.map(|element| element / norm),
+        }
+    }
+
+    // Bends the unit vector `self` through a surface with unit `normal` facing against it.
+    // `eta` is the ratio of refractive indices, outside over inside.
+    // None on total internal reflection.
+    pub fn refract(&self, normal: &Self, eta: T) -> Option<Self> {
+        let cos_in = -self.dot(normal);
+        let k = T::one() - eta * eta * (T::one() - cos_in * cos_in);
+        if k < T::zero() {
+            return None;
+        }
+        let scale = eta * cos_in - k.sqrt();
+        Some(Vector {
+            data: std::array::from_fn(|i| self.data[i] * eta + normal.data[i] * scale),
+        })
+    }
 }
 
 // Add impl block
@@ -109,6 +205,58 @@ where
     }
 }
 
+impl<T, const N: usize> Neg for Vector<T, N>
+where
+    T: Num + Sum + Copy + Display + Neg<Output = T>,
+{
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Vector {
+            data: self.data.map(|element| -element),
+        }
+    }
+}
+
+impl<T, const N: usize> AddAssign for Vector<T, N>
+where
+    T: Num + Sum + Copy + Display,
+{
+    fn add_assign(&mut self, other: Self) {
+        for i in 0..N {
+            self.data[i] = self.data[i] + other.data[i];
+        }
+    }
+}
+
+impl<T, const N: usize> SubAssign for Vector<T, N>
+where
+    T: Num + Sum + Copy + Display,
+{
+    fn sub_assign(&mut self, other: Self) {
+        for i in 0..N {
+            self.data[i] = self.data[i] - other.data[i];
+        }
+    }
+}
+
+impl<T, const N: usize> MulAssign<T> for Vector<T, N>
+where
+    T: Num + Sum + Copy + Display,
+{
+    fn mul_assign(&mut self, rhs: T) {
+        self.data = self.data.map(|element| element * rhs);
+    }
+}
+
+impl<T, const N: usize> DivAssign<T> for Vector<T, N>
+where
+    T: Num + Sum + Copy + Display,
+{
+    fn div_assign(&mut self, rhs: T) {
+        self.data = self.data.map(|element| element / rhs);
+    }
+}
+
 // Display impl block
 impl<T, const N: usize> Display for Vector<T, N>
 where
@@ -215,4 +363,81 @@ mod test {
         let res = vec_one.dot(&vec_two);
         assert_eq!(res, 32.);
     }
+
+    #[test]
+    fn vec3_norm_float() {
+        let vec = Vector::new([2., 3., 6.]);
+
+        assert_eq!(49., vec.norm_squared());
+        assert_eq!(7., vec.norm());
+        assert_eq!(Vector::new([2. / 7., 3. / 7., 6. / 7.]), vec.normalize());
+        assert_eq!(
+            Vector::new([0., 0., 0.]),
+            Vector::new([0., 0., 0.]).normalize()
+        );
+    }
+
+    #[test]
+    fn vec3_lerp_float() {
+        let vec_one = Vector::new([1., 2., 3.]);
+        let vec_two = Vector::new([5., 6., 7.]);
+
+        assert_eq!(vec_one, vec_one.lerp(&vec_two, 0.));
+        assert_eq!(Vector::new([2., 3., 4.]), vec_one.lerp(&vec_two, 0.25));
+        assert_eq!(vec_two, vec_one.lerp(&vec_two, 1.));
+    }
+
+    #[test]
+    fn vec3_reflect_float() {
+        let incoming = Vector::new([1., -1., 0.]);
+        let normal = Vector::new([0., 1., 0.]);
+
+        let res = incoming.reflect(&normal);
+        assert_eq!(Vector::new([1., 1., 0.]), res);
+    }
+
+    #[test]
+    fn vec3_refract_float() {
+        let incoming: Vector<f64, 3> = Vector::new([1., -1., 0.]).normalize();
+        let normal = Vector::new([0., 1., 0.]);
+
+        // Same medium on both sides goes straight through
+        let res = incoming.refract(&normal, 1.).unwrap();
+        assert!((res - incoming.clone()).norm() < 1e-12);
+
+        // Snell's law, sin out = eta * sin in
+        let res = incoming.refract(&normal, 1. / 1.5).unwrap();
+        let sin_in = incoming[0];
+        assert!((res[0] - sin_in / 1.5).abs() < 1e-12);
+        assert!((res.norm() - 1.).abs() < 1e-12);
+        assert!(res[1] < 0.);
+
+        // Past the critical angle going into the thinner medium
+        assert_eq!(None, incoming.refract(&normal, 1.5));
+    }
+
+    #[test]
+    fn vec3_min_max_abs_int() {
+        let vec_one = Vector::new([1, -5, 3]);
+        let vec_two = Vector::new([4, 2, -6]);
+
+        assert_eq!(Vector::new([1, -5, -6]), vec_one.min(&vec_two));
+        assert_eq!(Vector::new([4, 2, 3]), vec_one.max(&vec_two));
+        assert_eq!(Vector::new([1, 5, 3]), vec_one.abs());
+    }
+
+    #[test]
+    fn vec3_neg_and_assign_ops() {
+        let mut vec = Vector::new([1., 2., 3.]);
+        assert_eq!(Vector::new([-1., -2., -3.]), -vec.clone());
+
+        vec += Vector::new([4., 5., 6.]);
+        assert_eq!(Vector::new([5., 7., 9.]), vec);
+        vec -= Vector::new([1., 1., 1.]);
+        assert_eq!(Vector::new([4., 6., 8.]), vec);
+        vec *= 0.5;
+        assert_eq!(Vector::new([2., 3., 4.]), vec);
+        vec /= 2.;
+        assert_eq!(Vector::new([1., 1.5, 2.]), vec);
+    }
 }
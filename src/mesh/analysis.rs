@@ -0,0 +1,320 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::{math::Vector3, mesh::triangulate::polygon_normal, obj::ObjFile};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshReport {
+    // Only verticies used by a face are counted
+    pub verticies: usize,
+    pub edges: usize,
+    pub faces: usize,
+    // Edges shared by more than two faces
+    pub non_manifold_edges: Vec<(usize, usize)>,
+    // Holes, as the verticies around each one
+    pub boundary_loops: Vec<Vec<usize>>,
+    // Faces with repeated verticies or no area
+    pub degenerate_faces: Vec<usize>,
+    // Faces using the same verticies as an earlier face
+    pub duplicate_faces: Vec<usize>,
+    // Edges both of whose faces run the same way along them, so one of them is flipped
+    pub inconsistent_edges: Vec<(usize, usize)>,
+    pub components: usize,
+    // V - E + F
+    pub euler_characteristic: i64,
+    // Handles summed over all components, None when the mesh isn't a manifold or
+    // isn't consistently oriented
+    pub genus: Option<usize>,
+}
+
+impl MeshReport {
+    // Closed, manifold and consistently wound
+    pub fn is_watertight(&self) -> bool {
+        self.boundary_loops.is_empty()
+            && self.non_manifold_edges.is_empty()
+            && self.inconsistent_edges.is_empty()
+    }
+}
+
+impl Display for MeshReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "verticies: {}", self.verticies)?;
+        writeln!(f, "edges: {}", self.edges)?;
+        writeln!(f, "faces: {}", self.faces)?;
+        writeln!(f, "components: {}", self.components)?;
+        writeln!(f, "boundary loops: {}", self.boundary_loops.len())?;
+        writeln!(f, "non-manifold edges: {}", self.non_manifold_edges.len())?;
+        writeln!(f, "degenerate faces: {}", self.degenerate_faces.len())?;
+        writeln!(f, "duplicate faces: {}", self.duplicate_faces.len())?;
+        writeln!(f, "inconsistent edges: {}", self.inconsistent_edges.len())?;
+        writeln!(f, "euler characteristic: {}", self.euler_characteristic)?;
+        match self.genus {
+            Some(genus) => writeln!(f, "genus: {}", genus),
+            None if !self.non_manifold_edges.is_empty() => writeln!(f, "genus: not a manifold"),
+            None => writeln!(f, "genus: not orientable"),
+        }
+    }
+}
+
+fn find(parents: &mut [usize], mut vertex: usize) -> usize {
+    while parents[vertex] != vertex {
+        parents[vertex] = parents[parents[vertex]];
+        vertex = parents[vertex];
+    }
+    vertex
+}
+
+fn is_degenerate(points: &[Vector3<f64>], distinct: usize) -> bool {
+    if distinct < 3 {
+        return true;
+    }
+    let normal = polygon_normal(points);
+    let longest = (0..points.len())
+        .map(|i| {
+            let edge = points[(i + 1) % points.len()].clone() - points[i].clone();
+            edge.dot(&edge)
+        })
+        .fold(0., f64::max);
+    // Twice the area against the longest edge squared, so scale doesn't matter
+    normal.norm() <= 1e-12 * longest
+}
+
+// Walks the open edges into loops. Verticies where several holes meet are left
+// through any edge not walked yet.
+fn boundary_loops(open: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, &(a, b)) in open.iter().enumerate() {
+        next.entry(a).or_default().push(index);
+        next.entry(b).or_default().push(index);
+    }
+    let mut walked = vec![false; open.len()];
+    let mut loops = Vec::new();
+    for start in 0..open.len() {
+        if walked[start] {
+            continue;
+        }
+        walked[start] = true;
+        let (first, mut vertex) = open[start];
+        let mut verticies = vec![first];
+        while vertex != first {
+            verticies.push(vertex);
+            let edge = next[&vertex].iter().copied().find(|&edge| !walked[edge]);
+            let Some(edge) = edge else {
+                break;
+            };
+            walked[edge] = true;
+            let (a, b) = open[edge];
+            vertex = if a == vertex { b } else { a };
+        }
+        loops.push(verticies);
+    }
+    loops
+}
+
+pub fn analyze(obj: &ObjFile) -> MeshReport {
+    let mut report = MeshReport::default();
+    let vertex_count = obj.verticies.len();
+    let mut used = vec![false; vertex_count];
+    let mut parents: Vec<usize> = (0..vertex_count).collect();
+    // Faces using each edge, and how many run along it from the lower index
+    let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for (index, face) in obj.faces.iter().enumerate() {
+        let verticies: Vec<usize> = face.verticies.iter().map(|c| c.vertex).collect();
+        if verticies.iter().any(|&vertex| vertex >= vertex_count) {
+            continue;
+        }
+        report.faces += 1;
+        let mut sorted = verticies.clone();
+        sorted.sort_unstable();
+        if !seen.insert(sorted.clone()) {
+            report.duplicate_faces.push(index);
+        }
+        sorted.dedup();
+        let points: Vec<Vector3<f64>> = verticies
+            .iter()
+            .map(|&vertex| obj.verticies[vertex].clone())
+            .collect();
+        if is_degenerate(&points, sorted.len()) {
+            report.degenerate_faces.push(index);
+        }
+
+        for (i, &a) in verticies.iter().enumerate() {
+            let b = verticies[(i + 1) % verticies.len()];
+            used[a] = true;
+            let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+            parents[root_a] = root_b;
+            if a == b {
+                continue;
+            }
+            let entry = edges.entry((a.min(b), a.max(b))).or_default();
+            entry.0 += 1;
+            if a < b {
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let mut open = Vec::new();
+    let mut sorted_edges: Vec<_> = edges.into_iter().collect();
+    sorted_edges.sort_unstable();
+    for (edge, (faces, forward)) in sorted_edges {
+        report.edges += 1;
+        match faces {
+            1 => open.push(edge),
+            2 if forward != 1 => report.inconsistent_edges.push(edge),
+            2 => {}
+            _ => report.non_manifold_edges.push(edge),
+        }
+    }
+    report.boundary_loops = boundary_loops(&open);
+
+    report.verticies = used.iter().filter(|&&used| used).count();
+    let roots: HashSet<usize> = (0..vertex_count)
+        .filter(|&vertex| used[vertex])
+        .map(|vertex| find(&mut parents, vertex))
+        .collect();
+    report.components = roots.len();
+    report.euler_characteristic =
+        report.verticies as i64 - report.edges as i64 + report.faces as i64;
+
+    // χ = 2C - 2g - b for an orientable manifold with C components and b holes.
+    // A Klein bottle has χ = 0 too, so inconsistent winding leaves the genus unknown.
+    if report.non_manifold_edges.is_empty() && report.inconsistent_edges.is_empty() {
+        let twice_genus = 2 * report.components as i64
+            - report.euler_characteristic
+            - report.boundary_loops.len() as i64;
+        if twice_genus >= 0 && twice_genus % 2 == 0 {
+            report.genus = Some(twice_genus as usize / 2);
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        mesh::analysis::analyze,
+        obj::{ObjParseOptions, parse_obj_str},
+    };
+
+    const CUBE: &str =
+        "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8
+";
+
+    #[test]
+    fn analysis_closed_cube() {
+        let obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        let report = analyze(&obj);
+        assert_eq!((8, 12, 6), (report.verticies, report.edges, report.faces));
+        assert_eq!(2, report.euler_characteristic);
+        assert_eq!(Some(0), report.genus);
+        assert_eq!(1, report.components);
+        assert!(report.is_watertight());
+        assert!(report.degenerate_faces.is_empty());
+        assert!(report.duplicate_faces.is_empty());
+    }
+
+    #[test]
+    fn analysis_finds_problems() {
+        // The cube without its top, with one side flipped, a repeated face, a sliver
+        // and a separate triangle
+        let obj_str = CUBE.replace("f 5 6 7 8\n", "")
+            + "f 2 6 3\nf 1 2 6 5\nf 1 2 2\nv 5 0 0\nv 6 0 0\nv 5 1 0\nf 9 10 11\n";
+        let obj_str = obj_str.replace("f 3 4 8 7", "f 7 8 4 3");
+        let obj = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        let report = analyze(&obj);
+        assert_eq!(2, report.components);
+        assert_eq!(vec![6], report.duplicate_faces);
+        assert_eq!(vec![7], report.degenerate_faces);
+        // The extra triangle puts a third face on two edges
+        assert!(report.non_manifold_edges.contains(&(1, 5)));
+        assert!(report.inconsistent_edges.contains(&(2, 3)));
+        assert_eq!(None, report.genus);
+        assert!(!report.is_watertight());
+    }
+
+    #[test]
+    fn analysis_holes_and_handles() {
+        // A square with a square hole is an annulus
+        let obj_str = "v 0 0 0\nv 3 0 0\nv 3 3 0\nv 0 3 0\nv 1 1 0\nv 2 1 0\nv 2 2 0\nv 1 2 0
+f 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8
+";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let report = analyze(&obj);
+        assert_eq!(2, report.boundary_loops.len());
+        assert!(report.boundary_loops.iter().all(|l| l.len() == 4));
+        assert_eq!(0, report.euler_characteristic);
+        assert_eq!(Some(0), report.genus);
+
+        // A torus made of a 3 x 3 grid of quads
+        let mut torus = String::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                let (u, v) = (i as f64 * 2.1, j as f64 * 2.1);
+                let radius = 3. + v.cos();
+                torus.push_str(&format!(
+                    "v {} {} {}\n",
+                    radius * u.cos(),
+                    radius * u.sin(),
+                    v.sin()
+                ));
+            }
+        }
+        let index = |i: usize, j: usize| (i % 3) * 3 + j % 3 + 1;
+        for i in 0..3 {
+            for j in 0..3 {
+                torus.push_str(&format!(
+                    "f {} {} {} {}\n",
+                    index(i, j),
+                    index(i + 1, j),
+                    index(i + 1, j + 1),
+                    index(i, j + 1)
+                ));
+            }
+        }
+        let obj = parse_obj_str(&torus, &ObjParseOptions::default()).unwrap();
+        let report = analyze(&obj);
+        assert_eq!(0, report.euler_characteristic);
+        assert_eq!(Some(1), report.genus);
+        assert!(report.is_watertight());
+    }
+
+    #[test]
+    fn analysis_klein_bottle() {
+        // The torus grid again, with one pair of sides glued back to front
+        let mut obj_str = String::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                obj_str.push_str(&format!("v {} {} 0\n", i, j));
+            }
+        }
+        let index = |i: usize, j: usize| match i {
+            3 => (3 - j % 3) % 3 + 1,
+            _ => i * 3 + j % 3 + 1,
+        };
+        for i in 0..3 {
+            for j in 0..3 {
+                obj_str.push_str(&format!(
+                    "f {} {} {} {}\n",
+                    index(i, j),
+                    index(i + 1, j),
+                    index(i + 1, j + 1),
+                    index(i, j + 1)
+                ));
+            }
+        }
+        let obj = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        let report = analyze(&obj);
+        assert_eq!(0, report.euler_characteristic);
+        assert!(report.boundary_loops.is_empty());
+        assert!(report.non_manifold_edges.is_empty());
+        assert!(!report.inconsistent_edges.is_empty());
+        assert_eq!(None, report.genus);
+        assert!(report.to_string().contains("genus: not orientable"));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{math::Vector3, obj::ObjFile};
+
+// Axis aligned bounding box
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f64>,
+    pub max: Vector3<f64>,
+}
+
+impl Aabb {
+    // None when there are no points
+    pub fn from_points(points: &[Vector3<f64>]) -> Option<Self> {
+        let first = points.first()?.get_data();
+        let (mut min, mut max) = (first, first);
+        for point in &points[1..] {
+            for (axis, value) in point.get_data().into_iter().enumerate() {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        Some(Aabb {
+            min: Vector3::new(min),
+            max: Vector3::new(max),
+        })
+    }
+
+    pub fn center(&self) -> Vector3<f64> {
+        (self.min.clone() + self.max.clone()) / 2.
+    }
+
+    pub fn size(&self) -> Vector3<f64> {
+        self.max.clone() - self.min.clone()
+    }
+
+    pub fn contains(&self, point: &Vector3<f64>) -> bool {
+        (0..3).all(|axis| self.min[axis] <= point[axis] && point[axis] <= self.max[axis])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vector3<f64>,
+    pub radius: f64,
+}
+
+fn distance(a: &Vector3<f64>, b: &Vector3<f64>) -> f64 {
+    let difference = a.clone() - b.clone();
+    difference.norm()
+}
+
+impl BoundingSphere {
+    // Ritter's algorithm. Not the smallest sphere, but within a few percent of it.
+    pub fn from_points(points: &[Vector3<f64>]) -> Option<Self> {
+        let farthest_from = |from: &Vector3<f64>| {
+            points
+                .iter()
+                .max_by(|a, b| distance(from, a).total_cmp(&distance(from, b)))
+        };
+        let one = farthest_from(points.first()?)?;
+        let two = farthest_from(one)?;
+        let mut sphere = BoundingSphere {
+            center: (one.clone() + two.clone()) / 2.,
+            radius: distance(one, two) / 2.,
+        };
+        // Grow just enough to take in anything left outside
+        for point in points {
+            let d = distance(&sphere.center, point);
+            if d > sphere.radius {
+                let radius = (sphere.radius + d) / 2.;
+                let shift =
+                    (point.clone() - sphere.center.clone()) * ((radius - sphere.radius) / d);
+                sphere.center = sphere.center.clone() + shift;
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+
+    pub fn contains(&self, point: &Vector3<f64>) -> bool {
+        distance(&self.center, point) <= self.radius
+    }
+}
+
+// Moves `center` to the origin, then scales by `scale`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitTransform {
+    pub center: Vector3<f64>,
+    pub scale: f64,
+}
+
+impl FitTransform {
+    // Largest side of the box becomes -1..1
+    pub fn to_unit_cube(aabb: &Aabb) -> Self {
+        let largest = aabb.size().get_data().into_iter().fold(0., f64::max);
+        FitTransform {
+            center: aabb.center(),
+            scale: if largest > 0. { 2. / largest } else { 1. },
+        }
+    }
+
+    // The sphere ends up at the origin with the given radius. Unlike the box, a sphere
+    // still fits after the mesh is rotated.
+    pub fn to_sphere(sphere: &BoundingSphere, radius: f64) -> Self {
+        FitTransform {
+            center: sphere.center.clone(),
+            scale: if sphere.radius > 0. {
+                radius / sphere.radius
+            } else {
+                1.
+            },
+        }
+    }
+
+    pub fn apply(&self, point: &Vector3<f64>) -> Vector3<f64> {
+        (point.clone() - self.center.clone()) * self.scale
+    }
+}
+
+pub fn bounds(obj: &ObjFile) -> Option<Aabb> {
+    Aabb::from_points(&obj.verticies)
+}
+
+pub fn bounding_sphere(obj: &ObjFile) -> Option<BoundingSphere> {
+    BoundingSphere::from_points(&obj.verticies)
+}
+
+// Centers the mesh on the origin and scales it into -1..1, keeping its proportions.
+// Normals only change length under uniform scaling, so they are left alone.
+pub fn normalize(obj: &mut ObjFile) {
+    let Some(aabb) = bounds(obj) else {
+        return;
+    };
+    let fit = FitTransform::to_unit_cube(&aabb);
+    for vertex in obj.verticies.iter_mut() {
+        *vertex = fit.apply(vertex);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        mesh::bounds::{Aabb, BoundingSphere, FitTransform, bounds, normalize},
+        obj::ObjFile,
+    };
+
+    fn points() -> Vec<Vector3<f64>> {
+        vec![
+            Vector3::new([10., 20., 30.]),
+            Vector3::new([14., 20., 30.]),
+            Vector3::new([10., 22., 30.]),
+            Vector3::new([12., 21., 31.]),
+            Vector3::new([11., 20.5, 29.]),
+        ]
+    }
+
+    #[test]
+    fn bounds_aabb() {
+        let aabb = Aabb::from_points(&points()).unwrap();
+        assert_eq!(Vector3::new([10., 20., 29.]), aabb.min);
+        assert_eq!(Vector3::new([14., 22., 31.]), aabb.max);
+        assert_eq!(Vector3::new([12., 21., 30.]), aabb.center());
+        assert!(points().iter().all(|point| aabb.contains(point)));
+        assert!(!aabb.contains(&Vector3::new([0., 0., 0.])));
+        assert!(Aabb::from_points(&[]).is_none());
+    }
+
+    #[test]
+    fn bounds_sphere() {
+        let sphere = BoundingSphere::from_points(&points()).unwrap();
+        for point in points() {
+            assert!(sphere.contains(&point));
+        }
+        // Half the longest distance between points is a lower bound
+        assert!(sphere.radius >= 20f64.sqrt() / 2.);
+        assert!(sphere.radius < 3.);
+
+        let fit = FitTransform::to_sphere(&sphere, 1.);
+        for point in points() {
+            let fitted = fit.apply(&point);
+            assert!(fitted.norm() <= 1. + 1e-9);
+        }
+    }
+
+    #[test]
+    fn bounds_normalize() {
+        let mut obj = ObjFile {
+            verticies: points(),
+            ..Default::default()
+        };
+        normalize(&mut obj);
+        let aabb = bounds(&obj).unwrap();
+        assert_eq!(Vector3::new([-1., -0.5, -0.5]), aabb.min);
+        assert_eq!(Vector3::new([1., 0.5, 0.5]), aabb.max);
+    }
+}
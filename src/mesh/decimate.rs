@@ -0,0 +1,631 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::{
+    math::Vector3,
+    mesh::copy_face_ranges,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+#[derive(Debug, Clone)]
+pub struct DecimateOptions {
+    // Stop once no more than this many triangles are left
+    pub target_triangles: usize,
+    // Stop before any collapse that costs more than this, in squared distance to the original
+    // surface. None collapses until the target is reached.
+    pub max_error: Option<f64>,
+    // Verticies on open edges never move
+    pub preserve_boundaries: bool,
+    // Verticies where the texture coordinates split never move
+    pub preserve_uv_seams: bool,
+}
+
+impl Default for DecimateOptions {
+    fn default() -> Self {
+        DecimateOptions {
+            target_triangles: 0,
+            max_error: None,
+            preserve_boundaries: true,
+            preserve_uv_seams: true,
+        }
+    }
+}
+
+// Symmetric 4x4 error matrix, upper triangle row by row
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    // Squared distance to the plane a x + b y + c z + d = 0, times `weight`
+    fn plane(normal: &Vector3<f64>, d: f64, weight: f64) -> Self {
+        let [a, b, c] = normal.get_data();
+        Quadric(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        )
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        let mut sum = self.0;
+        for (value, other) in sum.iter_mut().zip(other.0) {
+            *value += other;
+        }
+        Quadric(sum)
+    }
+
+    fn error(&self, point: &Vector3<f64>) -> f64 {
+        let q = self.0;
+        let [x, y, z] = point.get_data();
+        q[0] * x * x
+            + 2. * q[1] * x * y
+            + 2. * q[2] * x * z
+            + 2. * q[3] * x
+            + q[4] * y * y
+            + 2. * q[5] * y * z
+            + 2. * q[6] * y
+            + q[7] * z * z
+            + 2. * q[8] * z
+            + q[9]
+    }
+
+    // The point with the least error, None when the matrix can't be inverted
+    fn optimal(&self) -> Option<Vector3<f64>> {
+        let q = self.0;
+        let m = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let rhs = [-q[3], -q[6], -q[8]];
+        let det = |m: &[[f64; 3]; 3]| {
+            m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+                - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+                + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+        };
+        let determinant = det(&m);
+        let scale = m.iter().flatten().fold(0., |max: f64, v| max.max(v.abs()));
+        if determinant.abs() <= 1e-10 * scale.powi(3) || scale == 0. {
+            return None;
+        }
+        // Cramer's rule
+        let solution: [f64; 3] = std::array::from_fn(|column| {
+            let mut replaced = m;
+            for row in 0..3 {
+                replaced[row][column] = rhs[row];
+            }
+            det(&replaced) / determinant
+        });
+        Some(Vector3::new(solution))
+    }
+}
+
+// Heap entry, cheapest collapse first. Stale entries are skipped using the stamps.
+struct Candidate {
+    cost: f64,
+    edge: (usize, usize),
+    stamps: (usize, usize),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.edge.cmp(&self.edge))
+    }
+}
+
+// Collapse `remove` into `keep`, which moves to `position`
+struct Plan {
+    keep: usize,
+    remove: usize,
+    position: Vector3<f64>,
+    cost: f64,
+}
+
+struct Decimator<'a> {
+    obj: &'a ObjFile,
+    options: &'a DecimateOptions,
+    positions: Vec<Vector3<f64>>,
+    quadrics: Vec<Quadric>,
+    pinned: Vec<bool>,
+    stamps: Vec<usize>,
+    // Triangle corners, with the original face each came from
+    triangles: Vec<([FaceVertex; 3], usize)>,
+    alive: Vec<bool>,
+    alive_count: usize,
+    // Triangles around every vertex
+    around: Vec<Vec<usize>>,
+    texture_coords: Vec<Vector3<f64>>,
+    normals: Vec<Vector3<f64>>,
+}
+
+fn triangle_normal(points: [&Vector3<f64>; 3]) -> Vector3<f64> {
+    (points[1].clone() - points[0].clone()).cross(&(points[2].clone() - points[0].clone()))
+}
+
+impl<'a> Decimator<'a> {
+    fn new(obj: &'a ObjFile, options: &'a DecimateOptions) -> Self {
+        let vertex_count = obj.verticies.len();
+        let triangles: Vec<([FaceVertex; 3], usize)> = obj
+            .triangles
+            .iter()
+            .filter(|t| {
+                [t.one, t.two, t.three]
+                    .iter()
+                    .all(|c| c.vertex < vertex_count)
+            })
+            .map(|t| ([t.one, t.two, t.three], t.face))
+            .collect();
+        let mut around = vec![Vec::new(); vertex_count];
+        let mut quadrics = vec![Quadric::default(); vertex_count];
+        let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut textures: Vec<HashSet<Option<usize>>> = vec![HashSet::new(); vertex_count];
+        for (index, (corners, _)) in triangles.iter().enumerate() {
+            let points = corners.map(|c| &obj.verticies[c.vertex]);
+            let normal = triangle_normal(points);
+            let length = normal.norm();
+            if length > 0. {
+                let unit = normal / length;
+                let d = -unit.dot(points[0]);
+                // Weighted by area so slivers count for less
+                let quadric = Quadric::plane(&unit, d, length / 2.);
+                for corner in corners {
+                    quadrics[corner.vertex] = quadrics[corner.vertex].add(&quadric);
+                }
+            }
+            for (i, corner) in corners.iter().enumerate() {
+                around[corner.vertex].push(index);
+                textures[corner.vertex].insert(corner.texture);
+                let other = corners[(i + 1) % 3].vertex;
+                let key = (corner.vertex.min(other), corner.vertex.max(other));
+                *edges.entry(key).or_default() += 1;
+            }
+        }
+
+        let mut pinned = vec![false; vertex_count];
+        for (&(a, b), &count) in &edges {
+            // Open and non-manifold edges
+            if options.preserve_boundaries && count != 2 {
+                pinned[a] = true;
+                pinned[b] = true;
+            }
+        }
+        for (vertex, textures) in textures.iter().enumerate() {
+            if options.preserve_uv_seams && textures.len() > 1 {
+                pinned[vertex] = true;
+            }
+        }
+
+        let alive_count = triangles.len();
+        Decimator {
+            obj,
+            options,
+            positions: obj.verticies.clone(),
+            quadrics,
+            pinned,
+            stamps: vec![0; vertex_count],
+            alive: vec![true; triangles.len()],
+            alive_count,
+            triangles,
+            around,
+            texture_coords: obj.texture_coords.clone(),
+            normals: obj.normals.clone(),
+        }
+    }
+
+    fn neighbours(&self, vertex: usize) -> HashSet<usize> {
+        self.around[vertex]
+            .iter()
+            .flat_map(|&t| self.triangles[t].0.map(|c| c.vertex))
+            .filter(|&other| other != vertex)
+            .collect()
+    }
+
+    // The one texture coordinate and normal every corner of `vertex` uses, if there is one
+    fn attributes(&self, vertex: usize) -> Option<(Option<usize>, Option<usize>)> {
+        let mut attributes = self.around[vertex].iter().map(|&t| {
+            let corner = self.triangles[t].0.iter().find(|c| c.vertex == vertex);
+            corner.map(|c| (c.texture, c.normal))
+        });
+        let first = attributes.next()??;
+        attributes
+            .all(|other| other == Some(first))
+            .then_some(first)
+    }
+
+    fn plan(&self, a: usize, b: usize) -> Option<Plan> {
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let (keep, remove, position) = match (self.pinned[a], self.pinned[b]) {
+            (true, true) => return None,
+            // Only pinned verticies with one set of attributes can take the other's corners
+            (true, false) => (a, b, self.positions[a].clone()),
+            (false, true) => (b, a, self.positions[b].clone()),
+            (false, false) => {
+                let (pa, pb) = (&self.positions[a], &self.positions[b]);
+                let position = quadric.optimal().unwrap_or_else(|| {
+                    let middle = (pa.clone() + pb.clone()) / 2.;
+                    [pa.clone(), pb.clone(), middle]
+                        .into_iter()
+                        .min_by(|x, y| quadric.error(x).total_cmp(&quadric.error(y)))
+                        .unwrap()
+                });
+                (a, b, position)
+            }
+        };
+        if self.pinned[keep] && self.attributes(keep).is_none() {
+            return None;
+        }
+
+        // Link condition, the only shared neighbours are the tips of the shared triangles.
+        // Otherwise the collapse pinches the surface.
+        let shared: Vec<usize> = self.around[keep]
+            .iter()
+            .filter(|&&t| self.triangles[t].0.iter().any(|c| c.vertex == remove))
+            .copied()
+            .collect();
+        let tips: HashSet<usize> = shared
+            .iter()
+            .flat_map(|&t| self.triangles[t].0.map(|c| c.vertex))
+            .filter(|&v| v != keep && v != remove)
+            .collect();
+        let common: HashSet<usize> = self
+            .neighbours(keep)
+            .intersection(&self.neighbours(remove))
+            .copied()
+            .collect();
+        if common != tips || shared.is_empty() {
+            return None;
+        }
+
+        // No triangle may turn over
+        for &t in self.around[keep].iter().chain(&self.around[remove]) {
+            if shared.contains(&t) {
+                continue;
+            }
+            let before = self.triangles[t]
+                .0
+                .map(|c| self.positions[c.vertex].clone());
+            let after = self.triangles[t].0.map(|c| {
+                if c.vertex == keep || c.vertex == remove {
+                    position.clone()
+                } else {
+                    self.positions[c.vertex].clone()
+                }
+            });
+            let old_normal = triangle_normal([&before[0], &before[1], &before[2]]);
+            let new_normal = triangle_normal([&after[0], &after[1], &after[2]]);
+            if old_normal.dot(&new_normal) <= 0. {
+                return None;
+            }
+        }
+        Some(Plan {
+            keep,
+            remove,
+            cost: quadric.error(&position).max(0.),
+            position,
+        })
+    }
+
+    fn candidate(&self, a: usize, b: usize) -> Option<Candidate> {
+        let plan = self.plan(a, b)?;
+        Some(Candidate {
+            cost: plan.cost,
+            edge: (a, b),
+            stamps: (self.stamps[a], self.stamps[b]),
+        })
+    }
+
+    fn collapse(&mut self, plan: Plan) {
+        let Plan {
+            keep,
+            remove,
+            position,
+            ..
+        } = plan;
+        // Interpolate the attributes when both ends have a single set, so no seam is made
+        let merged = match (self.attributes(keep), self.attributes(remove)) {
+            (Some(kept), _) if self.pinned[keep] => Some(kept),
+            (Some((kt, kn)), Some((rt, rn))) => {
+                let (pk, pr) = (&self.positions[keep], &self.positions[remove]);
+                let edge = pr.clone() - pk.clone();
+                let length = edge.dot(&edge);
+                let t = if length > 0. {
+                    ((position.clone() - pk.clone()).dot(&edge) / length).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                let lerp =
+                    |list: &mut Vec<Vector3<f64>>, a: Option<usize>, b: Option<usize>| match (a, b)
+                    {
+                        (Some(a), Some(b)) if a != b => {
+                            let value = list[a].lerp(&list[b], t);
+                            list.push(value);
+                            Some(list.len() - 1)
+                        }
+                        (a, _) => a,
+                    };
+                let texture = lerp(&mut self.texture_coords, kt, rt);
+                let normal = lerp(&mut self.normals, kn, rn).inspect(|&n| {
+                    self.normals[n] = self.normals[n].normalize();
+                });
+                Some((texture, normal))
+            }
+            _ => None,
+        };
+
+        for t in std::mem::take(&mut self.around[remove]) {
+            if !self.alive[t] {
+                continue;
+            }
+            if self.triangles[t].0.iter().any(|c| c.vertex == keep) {
+                self.alive[t] = false;
+                self.alive_count -= 1;
+                for corner in self.triangles[t].0 {
+                    self.around[corner.vertex].retain(|&other| other != t);
+                }
+                continue;
+            }
+            for corner in self.triangles[t].0.iter_mut() {
+                if corner.vertex == remove {
+                    corner.vertex = keep;
+                }
+            }
+            self.around[keep].push(t);
+        }
+        if let Some((texture, normal)) = merged {
+            for &t in &self.around[keep] {
+                for corner in self.triangles[t].0.iter_mut() {
+                    if corner.vertex == keep {
+                        corner.texture = texture;
+                        corner.normal = normal;
+                    }
+                }
+            }
+        }
+        self.positions[keep] = position;
+        self.quadrics[keep] = self.quadrics[keep].add(&self.quadrics[remove]);
+        self.stamps[keep] += 1;
+        self.stamps[remove] += 1;
+    }
+
+    fn run(&mut self) {
+        let mut heap = BinaryHeap::new();
+        let mut seen = HashSet::new();
+        for (corners, _) in &self.triangles {
+            for i in 0..3 {
+                let (a, b) = (corners[i].vertex, corners[(i + 1) % 3].vertex);
+                if a != b && seen.insert((a.min(b), a.max(b))) {
+                    heap.extend(self.candidate(a, b));
+                }
+            }
+        }
+
+        while self.alive_count > self.options.target_triangles {
+            let Some(candidate) = heap.pop() else {
+                break;
+            };
+            let (a, b) = candidate.edge;
+            if candidate.stamps != (self.stamps[a], self.stamps[b]) {
+                continue;
+            }
+            if self
+                .options
+                .max_error
+                .is_some_and(|max| candidate.cost > max)
+            {
+                break;
+            }
+            let Some(plan) = self.plan(a, b) else {
+                continue;
+            };
+            let keep = plan.keep;
+            self.collapse(plan);
+            for neighbour in self.neighbours(keep) {
+                heap.extend(self.candidate(keep, neighbour));
+            }
+        }
+    }
+
+    // Drops dead triangles and unused verticies, texture coordinates and normals.
+    // Faces stay in the order of the faces they came from so the ranges can be mapped over.
+    fn finish(self) -> ObjFile {
+        let mut triangles: Vec<([FaceVertex; 3], usize)> = self
+            .triangles
+            .into_iter()
+            .zip(self.alive)
+            .filter_map(|(triangle, alive)| alive.then_some(triangle))
+            .collect();
+        triangles.sort_by_key(|(_, face)| *face);
+
+        let mut obj = ObjFile::default();
+        let mut vertex_map = HashMap::new();
+        let mut texture_map = HashMap::new();
+        let mut normal_map = HashMap::new();
+        for (corners, _) in &triangles {
+            let verticies = corners
+                .iter()
+                .map(|corner| {
+                    let vertex = *vertex_map.entry(corner.vertex).or_insert_with(|| {
+                        let color = self.obj.vertex_colors.get(corner.vertex).cloned();
+                        obj.push_vertex(self.positions[corner.vertex].clone(), color);
+                        obj.verticies.len() - 1
+                    });
+                    let texture = corner.texture.map(|t| {
+                        *texture_map.entry(t).or_insert_with(|| {
+                            obj.texture_coords.push(self.texture_coords[t].clone());
+                            obj.texture_coords.len() - 1
+                        })
+                    });
+                    let normal = corner.normal.map(|n| {
+                        *normal_map.entry(n).or_insert_with(|| {
+                            obj.normals.push(self.normals[n].clone());
+                            obj.normals.len() - 1
+                        })
+                    });
+                    FaceVertex {
+                        vertex,
+                        texture,
+                        normal,
+                        tangent: None,
+                    }
+                })
+                .collect();
+            obj.faces.push(Face { verticies });
+        }
+
+        let origins: Vec<usize> = triangles.iter().map(|(_, face)| *face).collect();
+        copy_face_ranges(self.obj, &mut obj, &origins);
+        obj.triangulate();
+        obj
+    }
+}
+
+// Garland and Heckbert's quadric error simplification over `obj.triangles`.
+// The result has one triangle per face.
+pub fn decimate(obj: &ObjFile, options: &DecimateOptions) -> ObjFile {
+    let mut decimator = Decimator::new(obj, options);
+    decimator.run();
+    decimator.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        math::Vector3,
+        mesh::decimate::{DecimateOptions, decimate},
+        obj::{
+            MaterialRange, ObjFile, ObjParseOptions, mtl::Material, parse_obj_file, parse_obj_str,
+        },
+    };
+
+    // n x n quads in the xy plane, split into triangles
+    fn grid(n: usize, bump: f64) -> ObjFile {
+        let mut obj_str = String::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                let z = if x == n / 2 && y == n / 2 { bump } else { 0. };
+                obj_str.push_str(&format!("v {} {} {}\nvt {} {}\n", x, y, z, x, y));
+            }
+        }
+        let index = |x: usize, y: usize| y * (n + 1) + x + 1;
+        for y in 0..n {
+            for x in 0..n {
+                let (a, b, c, d) = (
+                    index(x, y),
+                    index(x + 1, y),
+                    index(x + 1, y + 1),
+                    index(x, y + 1),
+                );
+                obj_str.push_str(&format!(
+                    "f {a}/{a} {b}/{b} {c}/{c}\nf {a}/{a} {c}/{c} {d}/{d}\n"
+                ));
+            }
+        }
+        parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn decimate_flat_grid() {
+        let obj = grid(8, 0.);
+        let options = DecimateOptions {
+            target_triangles: 0,
+            max_error: Some(1e-9),
+            ..Default::default()
+        };
+        let res = decimate(&obj, &options);
+        // A plane costs nothing to flatten, only the pinned border is left
+        assert!(res.triangles.len() < obj.triangles.len() / 2);
+        for vertex in &res.verticies {
+            assert_eq!(0., vertex.z());
+            let on_border = [vertex.x(), vertex.y()].iter().any(|&c| c == 0. || c == 8.);
+            assert!(on_border, "{} should have been removed", vertex);
+        }
+        // The texture still matches the positions
+        for face in &res.faces {
+            for corner in &face.verticies {
+                let uv = &res.texture_coords[corner.texture.unwrap()];
+                let position = &res.verticies[corner.vertex];
+                assert!((uv.x() - position.x()).abs() < 1e-9);
+                assert!((uv.y() - position.y()).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn decimate_keeps_features() {
+        let obj = grid(6, 3.);
+        let options = DecimateOptions {
+            target_triangles: 0,
+            max_error: Some(1e-6),
+            ..Default::default()
+        };
+        let res = decimate(&obj, &options);
+        assert!(res.triangles.len() < obj.triangles.len());
+        // The peak can't be collapsed for free
+        assert!(res.verticies.contains(&Vector3::new([3., 3., 3.])));
+    }
+
+    #[test]
+    fn decimate_diablo_to_target() {
+        let obj = parse_obj_file(Path::new("./assets/diablo.obj")).unwrap();
+        let target = obj.triangles.len() / 4;
+        let options = DecimateOptions {
+            target_triangles: target,
+            preserve_uv_seams: false,
+            ..Default::default()
+        };
+        let res = decimate(&obj, &options);
+        assert!(res.triangles.len() <= target + 1);
+        assert_eq!(res.faces.len(), res.triangles.len());
+        assert!(res.verticies.len() < obj.verticies.len());
+        let groups: usize = res.groups.iter().map(|group| group.faces.len()).sum();
+        assert!(groups > 0);
+    }
+
+    #[test]
+    fn decimate_keeps_materials() {
+        let mut obj = grid(8, 0.);
+        obj.materials.push(Material::new("stone"));
+        obj.material_ranges.push(MaterialRange {
+            material: 0,
+            faces: 0..obj.faces.len(),
+        });
+        let options = DecimateOptions {
+            target_triangles: 0,
+            max_error: Some(1e-9),
+            ..Default::default()
+        };
+        let res = decimate(&obj, &options);
+        assert!(res.faces.len() < obj.faces.len());
+        for face in 0..res.faces.len() {
+            assert_eq!("stone", res.face_material(face).unwrap().name);
+        }
+    }
+}
@@ -0,0 +1,667 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    math::Vector3,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HalfEdge {
+    pub origin: usize,
+    // The half edge running the other way in the next face, None on the border
+    pub twin: Option<usize>,
+    pub next: usize,
+    pub prev: usize,
+    pub face: usize,
+    // Texture coordinate and normal of the face at `origin`
+    pub corner: FaceVertex,
+}
+
+// Faces as loops of half edges, so neighbours are found without searching.
+// Removed faces and verticies are None, `to_obj` packs what is left.
+#[derive(Debug, Clone, Default)]
+pub struct HalfEdgeMesh {
+    pub positions: Vec<Vector3<f64>>,
+    // Empty or one per position
+    pub colors: Vec<Vector3<f64>>,
+    pub texture_coords: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub half_edges: Vec<HalfEdge>,
+    // One outgoing half edge per vertex. Border verticies keep the one on the border
+    // so walks around them start at one end of the fan.
+    pub vertex_edges: Vec<Option<usize>>,
+    pub face_edges: Vec<Option<usize>>,
+}
+
+fn lerp(list: &mut Vec<Vector3<f64>>, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => {
+            list.push((list[a].clone() + list[b].clone()) / 2.);
+            Some(list.len() - 1)
+        }
+        (a, _) => a,
+    }
+}
+
+impl HalfEdgeMesh {
+    // Fails on edges shared by more than two faces, or by two faces running the same way,
+    // and on verticies where separate fans of faces meet
+    pub fn from_obj(obj: &ObjFile) -> Result<Self> {
+        let mut mesh = HalfEdgeMesh {
+            positions: obj.verticies.clone(),
+            colors: obj.vertex_colors.clone(),
+            texture_coords: obj.texture_coords.clone(),
+            normals: obj.normals.clone(),
+            vertex_edges: vec![None; obj.verticies.len()],
+            ..Default::default()
+        };
+        let mut directed: HashMap<(usize, usize), usize> = HashMap::new();
+        for (index, face) in obj.faces.iter().enumerate() {
+            let corners = &face.verticies;
+            if corners.len() < 3 {
+                return Err(anyhow!("Face {} has fewer than 3 verticies", index));
+            }
+            let first = mesh.half_edges.len();
+            let count = corners.len();
+            for (i, corner) in corners.iter().enumerate() {
+                if corner.vertex >= obj.verticies.len() {
+                    return Err(anyhow!(
+                        "Face {} uses vertex {}, {} defined",
+                        index,
+                        corner.vertex,
+                        obj.verticies.len()
+                    ));
+                }
+                let next = corners[(i + 1) % count].vertex;
+                let edge = first + i;
+                if directed.insert((corner.vertex, next), edge).is_some() {
+                    return Err(anyhow!(
+                        "Edge {}-{} of face {} is already used, the mesh isn't a manifold \
+                         or its winding is inconsistent",
+                        corner.vertex,
+                        next,
+                        index
+                    ));
+                }
+                mesh.half_edges.push(HalfEdge {
+                    origin: corner.vertex,
+                    twin: None,
+                    next: first + (i + 1) % count,
+                    prev: first + (i + count - 1) % count,
+                    face: mesh.face_edges.len(),
+                    corner: *corner,
+                });
+            }
+            mesh.face_edges.push(Some(first));
+        }
+
+        for (&(a, b), &edge) in &directed {
+            mesh.half_edges[edge].twin = directed.get(&(b, a)).copied();
+        }
+        for (edge, half_edge) in mesh.half_edges.iter().enumerate() {
+            let slot = &mut mesh.vertex_edges[half_edge.origin];
+            if slot.is_none() || half_edge.twin.is_none() {
+                *slot = Some(edge);
+            }
+        }
+        // A walk around the vertex only sees one fan, the others would be missed
+        let mut outgoing_counts = vec![0; mesh.positions.len()];
+        for half_edge in &mesh.half_edges {
+            outgoing_counts[half_edge.origin] += 1;
+        }
+        for (vertex, &count) in outgoing_counts.iter().enumerate() {
+            if count != mesh.outgoing(vertex).len() {
+                return Err(anyhow!(
+                    "Vertex {} joins separate fans of faces, the mesh isn't a manifold",
+                    vertex
+                ));
+            }
+        }
+        Ok(mesh)
+    }
+
+    // Unused and removed verticies are dropped, the rest keep their order.
+    // Groups and materials aren't kept.
+    pub fn to_obj(&self) -> ObjFile {
+        let mut obj = ObjFile {
+            texture_coords: self.texture_coords.clone(),
+            normals: self.normals.clone(),
+            ..Default::default()
+        };
+        let mut used = vec![false; self.positions.len()];
+        for half_edge in &self.half_edges {
+            if self.face_edges[half_edge.face].is_some() {
+                used[half_edge.origin] = true;
+            }
+        }
+        let mut indices = HashMap::new();
+        for (vertex, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+            obj.push_vertex(
+                self.positions[vertex].clone(),
+                self.colors.get(vertex).cloned(),
+            );
+            indices.insert(vertex, obj.verticies.len() - 1);
+        }
+        for face in 0..self.face_edges.len() {
+            if self.face_edges[face].is_none() {
+                continue;
+            }
+            let verticies = self
+                .face_half_edges(face)
+                .into_iter()
+                .map(|edge| {
+                    let corner = self.half_edges[edge].corner;
+                    let vertex = indices[&self.half_edges[edge].origin];
+                    FaceVertex { vertex, ..corner }
+                })
+                .collect();
+            obj.faces.push(Face { verticies });
+        }
+        obj.triangulate();
+        obj
+    }
+
+    pub fn destination(&self, edge: usize) -> usize {
+        self.half_edges[self.half_edges[edge].next].origin
+    }
+
+    pub fn is_boundary_edge(&self, edge: usize) -> bool {
+        self.half_edges[edge].twin.is_none()
+    }
+
+    pub fn is_boundary_vertex(&self, vertex: usize) -> bool {
+        self.vertex_edges[vertex].is_some_and(|edge| self.is_boundary_edge(edge))
+    }
+
+    pub fn face_half_edges(&self, face: usize) -> Vec<usize> {
+        let Some(first) = self.face_edges[face] else {
+            return Vec::new();
+        };
+        let mut edges = vec![first];
+        let mut edge = self.half_edges[first].next;
+        while edge != first {
+            edges.push(edge);
+            edge = self.half_edges[edge].next;
+        }
+        edges
+    }
+
+    pub fn face_verticies(&self, face: usize) -> Vec<usize> {
+        self.face_half_edges(face)
+            .into_iter()
+            .map(|edge| self.half_edges[edge].origin)
+            .collect()
+    }
+
+    // Half edges leaving `vertex`, turning the same way as the faces are wound
+    pub fn outgoing(&self, vertex: usize) -> Vec<usize> {
+        let Some(first) = self.vertex_edges[vertex] else {
+            return Vec::new();
+        };
+        let mut edges = vec![first];
+        let mut edge = first;
+        while let Some(twin) = self.half_edges[self.half_edges[edge].prev].twin {
+            if twin == first {
+                break;
+            }
+            edges.push(twin);
+            edge = twin;
+        }
+        edges
+    }
+
+    // The verticies around `vertex` in order. Around a border vertex the ring starts and
+    // ends on the border.
+    pub fn one_ring(&self, vertex: usize) -> Vec<usize> {
+        let outgoing = self.outgoing(vertex);
+        let mut ring: Vec<usize> = outgoing
+            .iter()
+            .map(|&edge| self.destination(edge))
+            .collect();
+        if let Some(&last) = outgoing.last() {
+            let prev = self.half_edges[last].prev;
+            if self.is_boundary_edge(prev) {
+                ring.push(self.half_edges[prev].origin);
+            }
+        }
+        ring
+    }
+
+    pub fn faces_around(&self, vertex: usize) -> Vec<usize> {
+        self.outgoing(vertex)
+            .into_iter()
+            .map(|edge| self.half_edges[edge].face)
+            .collect()
+    }
+
+    // The border half edges around the hole next to `edge`, starting with it
+    pub fn boundary_walk(&self, edge: usize) -> Vec<usize> {
+        if !self.is_boundary_edge(edge) {
+            return Vec::new();
+        }
+        let mut edges = vec![edge];
+        let mut current = edge;
+        loop {
+            let mut next = self.half_edges[current].next;
+            while let Some(twin) = self.half_edges[next].twin {
+                next = self.half_edges[twin].next;
+            }
+            if next == edge || edges.len() > self.half_edges.len() {
+                return edges;
+            }
+            edges.push(next);
+            current = next;
+        }
+    }
+
+    // The verticies around every hole
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let mut walked = HashSet::new();
+        let mut loops = Vec::new();
+        for edge in 0..self.half_edges.len() {
+            let face = self.half_edges[edge].face;
+            if self.face_edges[face].is_none()
+                || !self.is_boundary_edge(edge)
+                || walked.contains(&edge)
+            {
+                continue;
+            }
+            let walk = self.boundary_walk(edge);
+            walked.extend(walk.iter().copied());
+            loops.push(
+                walk.into_iter()
+                    .map(|edge| self.half_edges[edge].origin)
+                    .collect(),
+            );
+        }
+        loops
+    }
+
+    fn link(&mut self, from: usize, to: usize) {
+        self.half_edges[from].next = to;
+        self.half_edges[to].prev = from;
+    }
+
+    fn set_twins(&mut self, a: Option<usize>, b: Option<usize>) {
+        if let Some(a) = a {
+            self.half_edges[a].twin = b;
+        }
+        if let Some(b) = b {
+            self.half_edges[b].twin = a;
+        }
+    }
+
+    // Picks the border edge out of `vertex` if there is one, from edges known to leave it
+    fn reset_vertex_edge(&mut self, vertex: usize, candidates: &[usize]) {
+        let live: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&edge| {
+                let half_edge = &self.half_edges[edge];
+                half_edge.origin == vertex && self.face_edges[half_edge.face].is_some()
+            })
+            .collect();
+        self.vertex_edges[vertex] = live
+            .iter()
+            .copied()
+            .find(|&edge| self.is_boundary_edge(edge))
+            .or(live.first().copied());
+    }
+
+    // Turns the edge shared by two triangles to join their other two corners
+    pub fn flip_edge(&mut self, edge: usize) -> Result<()> {
+        let twin = self.half_edges[edge]
+            .twin
+            .ok_or_else(|| anyhow!("Border edges can't be flipped"))?;
+        let [h, hn, hp] = [edge, self.half_edges[edge].next, self.half_edges[edge].prev];
+        let [t, tn, tp] = [twin, self.half_edges[twin].next, self.half_edges[twin].prev];
+        if self.half_edges[hn].next != hp || self.half_edges[tn].next != tp {
+            return Err(anyhow!("Only edges between two triangles can be flipped"));
+        }
+        let (a, b) = (self.half_edges[h].origin, self.half_edges[t].origin);
+        let (c, d) = (self.half_edges[hp].origin, self.half_edges[tp].origin);
+        if c == d || self.one_ring(c).contains(&d) {
+            return Err(anyhow!("Verticies {} and {} are already joined", c, d));
+        }
+
+        // (a, b, c) and (b, a, d) become (c, d, b) and (d, c, a)
+        let (f, g) = (self.half_edges[h].face, self.half_edges[t].face);
+        self.half_edges[h].origin = c;
+        self.half_edges[h].corner = self.half_edges[hp].corner;
+        self.half_edges[t].origin = d;
+        self.half_edges[t].corner = self.half_edges[tp].corner;
+        self.link(h, tp);
+        self.link(tp, hn);
+        self.link(hn, h);
+        self.link(t, hp);
+        self.link(hp, tn);
+        self.link(tn, t);
+        self.half_edges[tp].face = f;
+        self.half_edges[hp].face = g;
+        self.face_edges[f] = Some(h);
+        self.face_edges[g] = Some(t);
+        if self.vertex_edges[a] == Some(h) {
+            self.vertex_edges[a] = Some(tn);
+        }
+        if self.vertex_edges[b] == Some(t) {
+            self.vertex_edges[b] = Some(hn);
+        }
+        Ok(())
+    }
+
+    // Cuts `face` in two along a new edge between the origins of `from` and `to`
+    fn split_face(&mut self, from: usize, to: usize) {
+        let face = self.half_edges[from].face;
+        let (from_prev, to_prev) = (self.half_edges[from].prev, self.half_edges[to].prev);
+        let (u, w) = (self.half_edges[from].origin, self.half_edges[to].origin);
+        let new_face = self.face_edges.len();
+        let back = self.half_edges.len();
+        let forward = back + 1;
+        self.half_edges.push(HalfEdge {
+            origin: w,
+            twin: Some(forward),
+            next: from,
+            prev: to_prev,
+            face,
+            corner: self.half_edges[to].corner,
+        });
+        self.half_edges.push(HalfEdge {
+            origin: u,
+            twin: Some(back),
+            next: to,
+            prev: from_prev,
+            face: new_face,
+            corner: self.half_edges[from].corner,
+        });
+        self.link(to_prev, back);
+        self.link(back, from);
+        self.link(from_prev, forward);
+        self.link(forward, to);
+        self.face_edges[face] = Some(from);
+        self.face_edges.push(Some(to));
+        let mut edge = to;
+        while edge != forward {
+            self.half_edges[edge].face = new_face;
+            edge = self.half_edges[edge].next;
+        }
+        self.half_edges[forward].face = new_face;
+    }
+
+    // Adds a vertex in the middle of the edge, triangles on either side are split in two.
+    // Returns the new vertex.
+    pub fn split_edge(&mut self, edge: usize) -> usize {
+        let (a, b) = (self.half_edges[edge].origin, self.destination(edge));
+        let middle = self.positions.len();
+        self.positions
+            .push((self.positions[a].clone() + self.positions[b].clone()) / 2.);
+        if !self.colors.is_empty() {
+            self.colors
+                .push((self.colors[a].clone() + self.colors[b].clone()) / 2.);
+        }
+        self.vertex_edges.push(None);
+
+        let twin = self.half_edges[edge].twin;
+        let mut halves = Vec::new();
+        for side in [Some(edge), twin].into_iter().flatten() {
+            let next = self.half_edges[side].next;
+            let triangle = self.half_edges[self.half_edges[next].next].next == side;
+            let (start, end) = (self.half_edges[side].corner, self.half_edges[next].corner);
+            let corner = FaceVertex {
+                vertex: middle,
+                texture: lerp(&mut self.texture_coords, start.texture, end.texture),
+                normal: lerp(&mut self.normals, start.normal, end.normal),
+                tangent: None,
+            };
+            let half = self.half_edges.len();
+            self.half_edges.push(HalfEdge {
+                origin: middle,
+                twin: None,
+                next,
+                prev: side,
+                face: self.half_edges[side].face,
+                corner,
+            });
+            self.link(side, half);
+            self.link(half, next);
+            halves.push((half, triangle));
+        }
+        // Each original half edge now ends at the middle and pairs with the other side's new half
+        match halves.as_slice() {
+            [(h2, _), (t2, _)] => {
+                self.set_twins(Some(edge), Some(*t2));
+                self.set_twins(twin, Some(*h2));
+            }
+            _ => self.half_edges[halves[0].0].twin = None,
+        }
+        self.vertex_edges[middle] = Some(halves[0].0);
+
+        for (half, triangle) in halves {
+            if triangle {
+                let opposite = self.half_edges[self.half_edges[half].next].next;
+                self.split_face(half, opposite);
+            }
+        }
+        middle
+    }
+
+    // Merges the end of `edge` into its start, halfway between them. The faces on the edge
+    // must be triangles and the collapse may not fold the surface. Returns the kept vertex.
+    pub fn collapse_edge(&mut self, edge: usize) -> Result<usize> {
+        let (a, b) = (self.half_edges[edge].origin, self.destination(edge));
+        let twin = self.half_edges[edge].twin;
+        let mut sides = Vec::new();
+        for side in [Some(edge), twin].into_iter().flatten() {
+            let [next, prev] = [self.half_edges[side].next, self.half_edges[side].prev];
+            if self.half_edges[next].next != prev {
+                return Err(anyhow!("Only edges of triangles can be collapsed"));
+            }
+            sides.push([side, next, prev]);
+        }
+        if twin.is_some() && self.is_boundary_vertex(a) && self.is_boundary_vertex(b) {
+            return Err(anyhow!(
+                "Collapsing an inner edge between border verticies pinches the mesh"
+            ));
+        }
+        // Link condition, the ends may only share the tips of the triangles on the edge
+        let tips: HashSet<usize> = sides
+            .iter()
+            .map(|[_, _, prev]| self.half_edges[*prev].origin)
+            .collect();
+        let (ring_a, ring_b) = (self.one_ring(a), self.one_ring(b));
+        let shared: HashSet<usize> = ring_a
+            .iter()
+            .copied()
+            .filter(|vertex| ring_b.contains(vertex))
+            .collect();
+        if shared != tips || (ring_a.len() == 3 && ring_b.len() == 3 && twin.is_some()) {
+            return Err(anyhow!("Collapsing {}-{} would fold the surface", a, b));
+        }
+
+        let mut candidates: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut moved = self.outgoing(b);
+        candidates.insert(a, [self.outgoing(a), moved.clone()].concat());
+        for &tip in &tips {
+            candidates.insert(tip, self.outgoing(tip));
+        }
+        for [_, next, prev] in &sides {
+            let (outer_next, outer_prev) =
+                (self.half_edges[*next].twin, self.half_edges[*prev].twin);
+            self.set_twins(outer_next, outer_prev);
+            self.face_edges[self.half_edges[*next].face] = None;
+        }
+        moved.retain(|&half| self.face_edges[self.half_edges[half].face].is_some());
+        for half in moved {
+            self.half_edges[half].origin = a;
+            self.half_edges[half].corner.vertex = a;
+        }
+        self.positions[a] = (self.positions[a].clone() + self.positions[b].clone()) / 2.;
+        if !self.colors.is_empty() {
+            self.colors[a] = (self.colors[a].clone() + self.colors[b].clone()) / 2.;
+        }
+        self.vertex_edges[b] = None;
+        for (vertex, candidates) in candidates {
+            self.reset_vertex_edge(vertex, &candidates);
+        }
+        Ok(a)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        mesh::{analyze, half_edge::HalfEdgeMesh},
+        obj::{ObjParseOptions, parse_obj_str},
+    };
+
+    const CUBE: &str = "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1
+f 1/1 4/2 3/3 2/4\nf 5/1 6/2 7/3 8/4\nf 1/1 2/2 6/3 5/4\nf 2/1 3/2 7/3 6/4\nf 3/1 4/2 8/3 7/4\nf 4/1 1/2 5/3 8/4
+";
+
+    // A hexagon fanned around a middle vertex
+    fn hexagon() -> HalfEdgeMesh {
+        let mut obj_str = String::from("v 0 0 0\n");
+        for i in 0..6 {
+            let angle = i as f64 * std::f64::consts::PI / 3.;
+            obj_str.push_str(&format!("v {} {} 0\n", angle.cos(), angle.sin()));
+        }
+        for i in 0..6 {
+            obj_str.push_str(&format!("f 1 {} {}\n", i + 2, (i + 1) % 6 + 2));
+        }
+        let obj = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        HalfEdgeMesh::from_obj(&obj).unwrap()
+    }
+
+    fn find_edge(mesh: &HalfEdgeMesh, a: usize, b: usize) -> usize {
+        (0..mesh.half_edges.len())
+            .find(|&edge| {
+                mesh.face_edges[mesh.half_edges[edge].face].is_some()
+                    && mesh.half_edges[edge].origin == a
+                    && mesh.destination(edge) == b
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn half_edge_round_trip() {
+        let obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        let mesh = HalfEdgeMesh::from_obj(&obj).unwrap();
+        assert_eq!(24, mesh.half_edges.len());
+        assert!(mesh.half_edges.iter().all(|edge| edge.twin.is_some()));
+        assert!(mesh.boundary_loops().is_empty());
+        let res = mesh.to_obj();
+        assert_eq!(obj.verticies, res.verticies);
+        assert_eq!(obj.faces, res.faces);
+
+        // A third face on an edge
+        let bad = CUBE.to_string() + "f 1 2 7\n";
+        let obj = parse_obj_str(&bad, &ObjParseOptions::default()).unwrap();
+        assert!(HalfEdgeMesh::from_obj(&obj).is_err());
+    }
+
+    #[test]
+    fn half_edge_bow_tie() {
+        // Two triangles touching only at the first vertex
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv -1 0 0\nv -1 -1 0\nf 1 2 3\nf 1 4 5\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let err = HalfEdgeMesh::from_obj(&obj).err().unwrap();
+        assert!(err.to_string().starts_with("Vertex 0 joins separate fans"));
+
+        // The same two fans joined by a third triangle are fine
+        let obj_str = obj_str.to_string() + "f 1 3 4\n";
+        let obj = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        let mesh = HalfEdgeMesh::from_obj(&obj).unwrap();
+        assert_eq!(3, mesh.outgoing(0).len());
+    }
+
+    #[test]
+    fn half_edge_adjacency() {
+        let obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        let mesh = HalfEdgeMesh::from_obj(&obj).unwrap();
+        let mut ring = mesh.one_ring(0);
+        ring.sort_unstable();
+        assert_eq!(vec![1, 3, 4], ring);
+        assert_eq!(3, mesh.faces_around(6).len());
+        assert!(!mesh.is_boundary_vertex(0));
+
+        let mesh = hexagon();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], {
+            let mut ring = mesh.one_ring(0);
+            ring.sort_unstable();
+            ring
+        });
+        // Border verticies see the middle and their two border neighbours, in order
+        assert!(mesh.is_boundary_vertex(1));
+        assert_eq!(vec![2, 0, 6], mesh.one_ring(1));
+        let loops = mesh.boundary_loops();
+        assert_eq!(1, loops.len());
+        assert_eq!(6, loops[0].len());
+        let start = loops[0].iter().position(|&v| v == 1).unwrap();
+        assert_eq!(2, loops[0][(start + 1) % 6]);
+    }
+
+    #[test]
+    fn half_edge_flip() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let mut mesh = HalfEdgeMesh::from_obj(&obj).unwrap();
+        let diagonal = find_edge(&mesh, 0, 2);
+        mesh.flip_edge(diagonal).unwrap();
+        let mut diagonal = [mesh.half_edges[diagonal].origin, mesh.destination(diagonal)];
+        diagonal.sort_unstable();
+        assert_eq!([1, 3], diagonal);
+        let res = mesh.to_obj();
+        let report = analyze(&res);
+        assert!(report.inconsistent_edges.is_empty());
+        assert_eq!(1, report.boundary_loops.len());
+        assert!(res.faces.iter().all(|face| face.verticies.len() == 3));
+        // Both faces still face up
+        for face in &res.faces {
+            let [a, b, c] = [0, 1, 2].map(|i| res.verticies[face.verticies[i].vertex].clone());
+            let (u, v) = (b - a.clone(), c - a);
+            assert!(u.x() * v.y() - u.y() * v.x() > 0.);
+        }
+        assert!(mesh.flip_edge(find_edge(&mesh, 0, 1)).is_err());
+    }
+
+    #[test]
+    fn half_edge_split_and_collapse() {
+        let mut mesh = hexagon();
+        let spoke = find_edge(&mesh, 0, 1);
+        let middle = mesh.split_edge(spoke);
+        assert_eq!(Vector3::new([0.5, 0., 0.]), mesh.positions[middle]);
+        assert_eq!(4, mesh.one_ring(middle).len());
+        let res = mesh.to_obj();
+        assert_eq!(8, res.faces.len());
+        let report = analyze(&res);
+        assert_eq!(1, report.euler_characteristic);
+        assert!(report.inconsistent_edges.is_empty());
+
+        // A border edge gets one new face
+        let rim = find_edge(&mesh, 2, 3);
+        mesh.split_edge(rim);
+        assert_eq!(9, mesh.to_obj().faces.len());
+
+        let mut mesh = hexagon();
+        let kept = mesh.collapse_edge(find_edge(&mesh, 0, 1)).unwrap();
+        assert_eq!(0, kept);
+        assert_eq!(Vector3::new([0.5, 0., 0.]), mesh.positions[0]);
+        let res = mesh.to_obj();
+        assert_eq!(4, res.faces.len());
+        let report = analyze(&res);
+        assert_eq!(1, report.euler_characteristic);
+        assert_eq!(1, report.boundary_loops.len());
+        assert!(report.inconsistent_edges.is_empty());
+        assert_eq!(vec![2, 3, 4, 5, 6], {
+            let mut ring = mesh.one_ring(0);
+            ring.sort_unstable();
+            ring
+        });
+        // The spoke across now runs between border verticies
+        assert!(mesh.collapse_edge(find_edge(&mesh, 0, 4)).is_err());
+    }
+}
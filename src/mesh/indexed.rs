@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::Vector3,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct IndexOptions {
+    // Positions closer than this become one. 0 only merges exact duplicates.
+    pub weld_distance: f64,
+}
+
+// One index per corner pointing into parallel attribute lists, the layout GPUs want.
+// The attribute lists are either empty or as long as `positions`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexedMesh {
+    pub positions: Vec<Vector3<f64>>,
+    pub texture_coords: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub colors: Vec<Vector3<f64>>,
+    // Three per triangle
+    pub indices: Vec<usize>,
+}
+
+// Finds positions within `distance` of each other using a grid of that size,
+// so only the neighbouring cells have to be searched
+struct Welder {
+    distance: f64,
+    cells: HashMap<[i64; 3], Vec<usize>>,
+    exact: HashMap<[u64; 3], usize>,
+    positions: Vec<Vector3<f64>>,
+}
+
+impl Welder {
+    fn new(distance: f64) -> Self {
+        Welder {
+            distance,
+            cells: HashMap::new(),
+            exact: HashMap::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    fn weld(&mut self, position: &Vector3<f64>) -> usize {
+        if self.distance <= 0. {
+            // -0 and 0 are the same point
+            let key = position
+                .get_data()
+                .map(|component| (component + 0.).to_bits());
+            return *self.exact.entry(key).or_insert_with(|| {
+                self.positions.push(position.clone());
+                self.positions.len() - 1
+            });
+        }
+        let cell = position
+            .get_data()
+            .map(|component| (component / self.distance).floor() as i64);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let near = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+                    let found = self.cells.get(&near).and_then(|indices| {
+                        indices.iter().copied().find(|&index| {
+                            let offset = self.positions[index].clone() - position.clone();
+                            offset.dot(&offset) <= self.distance * self.distance
+                        })
+                    });
+                    if let Some(index) = found {
+                        return index;
+                    }
+                }
+            }
+        }
+        self.positions.push(position.clone());
+        let index = self.positions.len() - 1;
+        self.cells.entry(cell).or_default().push(index);
+        index
+    }
+}
+
+fn bits(value: Option<&Vector3<f64>>) -> Option<[u64; 3]> {
+    value.map(|value| value.get_data().map(|component| (component + 0.).to_bits()))
+}
+
+impl IndexedMesh {
+    // Every distinct (position, texture coordinate, normal) of `obj.triangles` becomes one
+    // vertex. Attributes are compared by value, so repeated "vt" and "vn" lines merge too.
+    // Corners missing an attribute the rest of the mesh has get zero.
+    pub fn from_obj(obj: &ObjFile, options: &IndexOptions) -> Self {
+        let corners: Vec<FaceVertex> = obj
+            .triangles
+            .iter()
+            .filter(|t| {
+                [t.one, t.two, t.three]
+                    .iter()
+                    .all(|c| c.vertex < obj.verticies.len())
+            })
+            .flat_map(|t| [t.one, t.two, t.three])
+            .collect();
+        let texture = |corner: &FaceVertex| corner.texture.and_then(|t| obj.texture_coords.get(t));
+        let normal = |corner: &FaceVertex| corner.normal.and_then(|n| obj.normals.get(n));
+        let textured = corners.iter().any(|corner| texture(corner).is_some());
+        let has_normals = corners.iter().any(|corner| normal(corner).is_some());
+        let colored = !obj.vertex_colors.is_empty();
+        let zero = || Vector3::new([0., 0., 0.]);
+
+        let mut mesh = IndexedMesh::default();
+        let mut welder = Welder::new(options.weld_distance);
+        let mut verticies = HashMap::new();
+        for corner in &corners {
+            let position = welder.weld(&obj.verticies[corner.vertex]);
+            let key = (position, bits(texture(corner)), bits(normal(corner)));
+            let index = *verticies.entry(key).or_insert_with(|| {
+                // Welded corners all take the position seen first
+                mesh.positions.push(welder.positions[position].clone());
+                if textured {
+                    let value = texture(corner).cloned().unwrap_or_else(zero);
+                    mesh.texture_coords.push(value);
+                }
+                if has_normals {
+                    let value = normal(corner).cloned().unwrap_or_else(zero);
+                    mesh.normals.push(value);
+                }
+                if colored {
+                    mesh.colors.push(obj.vertex_colors[corner.vertex].clone());
+                }
+                mesh.positions.len() - 1
+            });
+            mesh.indices.push(index);
+        }
+        mesh
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+    }
+
+    // One face per triangle, with the same index for the vertex and its attributes
+    pub fn to_obj(&self) -> ObjFile {
+        let mut obj = ObjFile {
+            verticies: self.positions.clone(),
+            vertex_colors: self.colors.clone(),
+            texture_coords: self.texture_coords.clone(),
+            normals: self.normals.clone(),
+            ..Default::default()
+        };
+        let textured = !self.texture_coords.is_empty();
+        let has_normals = !self.normals.is_empty();
+        obj.faces = self
+            .triangles()
+            .map(|triangle| Face {
+                verticies: triangle
+                    .iter()
+                    .map(|&index| FaceVertex {
+                        vertex: index,
+                        texture: textured.then_some(index),
+                        normal: has_normals.then_some(index),
+                        tangent: None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        obj.triangulate();
+        obj
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        mesh::{
+            NormalOptions, generate_normals,
+            indexed::{IndexOptions, IndexedMesh},
+        },
+        obj::{ObjParseOptions, parse_obj_str},
+    };
+
+    const CUBE: &str =
+        "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8
+";
+
+    #[test]
+    fn indexed_cube() {
+        let mut obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        let smooth = IndexedMesh::from_obj(&obj, &IndexOptions::default());
+        assert_eq!(8, smooth.positions.len());
+        assert_eq!(36, smooth.indices.len());
+        assert!(smooth.normals.is_empty());
+
+        // Hard edges give every face its own corners
+        let options = NormalOptions {
+            crease_angle: 0.5,
+            ..Default::default()
+        };
+        generate_normals(&mut obj, &options);
+        let flat = IndexedMesh::from_obj(&obj, &IndexOptions::default());
+        assert_eq!(24, flat.positions.len());
+        assert_eq!(24, flat.normals.len());
+        for [a, b, c] in flat.triangles() {
+            assert_eq!(flat.normals[a], flat.normals[b]);
+            assert_eq!(flat.normals[a], flat.normals[c]);
+        }
+    }
+
+    #[test]
+    fn indexed_welds_close_positions() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1e-9 0\nv 1 1 0\nv 0.0000000001 1 0\nvt 0 0\nvt 0 0\nf 1/1 2/1 3/1\nf 4/2 5/2 6/2\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let exact = IndexedMesh::from_obj(&obj, &IndexOptions::default());
+        assert_eq!(6, exact.positions.len());
+
+        let options = IndexOptions {
+            weld_distance: 1e-6,
+        };
+        let welded = IndexedMesh::from_obj(&obj, &options);
+        assert_eq!(4, welded.positions.len());
+        assert_eq!(vec![0, 1, 2, 1, 3, 2], welded.indices);
+        assert_eq!(Vector3::new([1., 0., 0.]), welded.positions[1]);
+        assert_eq!(4, welded.texture_coords.len());
+    }
+
+    #[test]
+    fn indexed_to_obj() {
+        let obj_str =
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nf 1/1 2/2 3/3\nf 1/1 3/3 2/1\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let mesh = IndexedMesh::from_obj(&obj, &IndexOptions::default());
+        // Vertex 2 has two texture coordinates
+        assert_eq!(4, mesh.positions.len());
+        let res = mesh.to_obj();
+        assert_eq!(2, res.triangles.len());
+        for (a, b) in obj.triangles.iter().zip(&res.triangles) {
+            for (a, b) in [(a.one, b.one), (a.two, b.two), (a.three, b.three)] {
+                assert_eq!(obj.verticies[a.vertex], res.verticies[b.vertex]);
+                assert_eq!(
+                    obj.texture_coords[a.texture.unwrap()],
+                    res.texture_coords[b.texture.unwrap()]
+                );
+            }
+        }
+    }
+}
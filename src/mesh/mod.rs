@@ -0,0 +1,64 @@
+pub mod analysis;
+pub mod bounds;
+pub mod decimate;
+pub mod half_edge;
+pub mod indexed;
+pub mod normals;
+pub mod subdivide;
+pub mod tangents;
+pub mod triangulate;
+
+pub use analysis::{MeshReport, analyze};
+pub use bounds::{Aabb, BoundingSphere, FitTransform, bounding_sphere, bounds, normalize};
+pub use decimate::{DecimateOptions, decimate};
+pub use half_edge::{HalfEdge, HalfEdgeMesh};
+pub use indexed::{IndexOptions, IndexedMesh};
+pub use normals::{NormalOptions, NormalWeighting, face_normals, generate_normals};
+pub use subdivide::{catmull_clark, loop_subdivide};
+pub use tangents::{bitangent, generate_tangents};
+pub use triangulate::triangulate;
+
+use std::ops::Range;
+
+use crate::obj::{MaterialRange, ObjFile, SmoothingGroup, SubMesh};
+
+// Carries the materials and the material, object, group and smoothing ranges of
+// `from` over to `to`. `origins` holds the face of `from` each face of `to` came
+// from, in order.
+pub(crate) fn copy_face_ranges(from: &ObjFile, to: &mut ObjFile, origins: &[usize]) {
+    let remap = |faces: &Range<usize>| {
+        origins.partition_point(|&f| f < faces.start)..origins.partition_point(|&f| f < faces.end)
+    };
+    // The ranges index into the materials, they only make sense together
+    to.materials = from.materials.clone();
+    to.material_ranges = from
+        .material_ranges
+        .iter()
+        .map(|range| MaterialRange {
+            material: range.material,
+            faces: remap(&range.faces),
+        })
+        .filter(|range| !range.faces.is_empty())
+        .collect();
+    let sub_meshes = |sub_meshes: &[SubMesh]| -> Vec<SubMesh> {
+        sub_meshes
+            .iter()
+            .map(|sub_mesh| SubMesh {
+                name: sub_mesh.name.clone(),
+                faces: remap(&sub_mesh.faces),
+            })
+            .filter(|sub_mesh| !sub_mesh.faces.is_empty())
+            .collect()
+    };
+    to.objects = sub_meshes(&from.objects);
+    to.groups = sub_meshes(&from.groups);
+    to.smoothing_groups = from
+        .smoothing_groups
+        .iter()
+        .map(|group| SmoothingGroup {
+            id: group.id,
+            faces: remap(&group.faces),
+        })
+        .filter(|group| !group.faces.is_empty())
+        .collect();
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::{math::Vector3, mesh::triangulate::polygon_normal, obj::ObjFile};
+
+// How much each face contributes to a smooth vertex normal
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NormalWeighting {
+    // Bigger faces pull harder
+    Area,
+    // Faces count by the angle they make at the vertex, so splitting a face doesn't change the result
+    #[default]
+    Angle,
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalOptions {
+    pub weighting: NormalWeighting,
+    // Radians. Faces meeting at a sharper angle keep a hard edge. PI smooths everything.
+    pub crease_angle: f64,
+    // Only faces in the same "s" group are smoothed together, and "s off" faces stay flat.
+    // Ignored when the file has no smoothing groups at all.
+    pub smoothing_groups: bool,
+}
+
+impl Default for NormalOptions {
+    fn default() -> Self {
+        NormalOptions {
+            weighting: NormalWeighting::default(),
+            crease_angle: PI,
+            smoothing_groups: true,
+        }
+    }
+}
+
+// Positions of a face's corners, None when one is out of range
+fn face_points(obj: &ObjFile, face: usize) -> Option<Vec<Vector3<f64>>> {
+    obj.faces[face]
+        .verticies
+        .iter()
+        .map(|corner| obj.verticies.get(corner.vertex).cloned())
+        .collect()
+}
+
+// Unit normal of every face, following the winding. Zero for degenerate faces.
+pub fn face_normals(obj: &ObjFile) -> Vec<Vector3<f64>> {
+    (0..obj.faces.len())
+        .map(|face| {
+            face_points(obj, face)
+                .map(|points| polygon_normal(&points).normalize())
+                .unwrap_or_else(|| Vector3::new([0., 0., 0.]))
+        })
+        .collect()
+}
+
+// Angle of the polygon at `corner`
+fn corner_angle(points: &[Vector3<f64>], corner: usize) -> f64 {
+    let n = points.len();
+    let prev = (points[(corner + n - 1) % n].clone() - points[corner].clone()).normalize();
+    let next = (points[(corner + 1) % n].clone() - points[corner].clone()).normalize();
+    prev.dot(&next).clamp(-1., 1.).acos()
+}
+
+// Replaces `obj.normals` with generated ones and points every face corner at its normal.
+// Corners whose smoothed normals come out equal share one entry.
+pub fn generate_normals(obj: &mut ObjFile, options: &NormalOptions) {
+    let face_count = obj.faces.len();
+    let unit_normals = face_normals(obj);
+    let points: Vec<Option<Vec<Vector3<f64>>>> =
+        (0..face_count).map(|face| face_points(obj, face)).collect();
+    let groups: Option<Vec<u32>> = (options.smoothing_groups && !obj.smoothing_groups.is_empty())
+        .then(|| {
+            (0..face_count)
+                .map(|face| obj.face_smoothing_group(face))
+                .collect()
+        });
+    let min_cos = options.crease_angle.cos();
+
+    // What each face adds to the normal of each of its corners
+    let contributions: Vec<Vec<Vector3<f64>>> = (0..face_count)
+        .map(|face| {
+            let corners = obj.faces[face].verticies.len();
+            let Some(points) = &points[face] else {
+                return vec![Vector3::new([0., 0., 0.]); corners];
+            };
+            (0..corners)
+                .map(|corner| match options.weighting {
+                    // Newell's normal is twice the area long
+                    NormalWeighting::Area => polygon_normal(points) * 0.5,
+                    NormalWeighting::Angle => {
+                        unit_normals[face].clone() * corner_angle(points, corner)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut incident: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for (face_index, face) in obj.faces.iter().enumerate() {
+        for (corner, face_vertex) in face.verticies.iter().enumerate() {
+            incident
+                .entry(face_vertex.vertex)
+                .or_default()
+                .push((face_index, corner));
+        }
+    }
+
+    let mut normals: Vec<Vector3<f64>> = Vec::new();
+    let mut shared: HashMap<(usize, [u64; 3]), usize> = HashMap::new();
+    for face in 0..face_count {
+        for corner in 0..obj.faces[face].verticies.len() {
+            let vertex = obj.faces[face].verticies[corner].vertex;
+            let smooths_with = |other: usize| {
+                if other == face {
+                    return true;
+                }
+                if let Some(groups) = &groups
+                    && (groups[face] == 0 || groups[face] != groups[other])
+                {
+                    return false;
+                }
+                unit_normals[face].dot(&unit_normals[other]) >= min_cos
+            };
+            let mut sum = Vector3::new([0., 0., 0.]);
+            for &(other, other_corner) in &incident[&vertex] {
+                if smooths_with(other) {
+                    sum += contributions[other][other_corner].clone();
+                }
+            }
+            let normal = sum.normalize();
+            // -0 and 0 are the same direction
+            let key = (vertex, normal.get_data().map(|c| (c + 0.).to_bits()));
+            let index = *shared.entry(key).or_insert_with(|| {
+                normals.push(normal);
+                normals.len() - 1
+            });
+            obj.faces[face].verticies[corner].normal = Some(index);
+        }
+    }
+    obj.normals = normals;
+    obj.triangulate();
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::{
+        math::Vector3,
+        mesh::normals::{NormalOptions, NormalWeighting, face_normals, generate_normals},
+        obj::{ObjFile, ObjParseOptions, parse_obj_str},
+    };
+
+    const CUBE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8\n";
+
+    fn cube() -> ObjFile {
+        parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap()
+    }
+
+    fn assert_close(expected: [f64; 3], res: &Vector3<f64>) {
+        for (a, b) in expected.iter().zip(res.get_data()) {
+            assert!((a - b).abs() < 1e-9, "{:?} != {}", expected, res);
+        }
+    }
+
+    #[test]
+    fn normals_faces() {
+        let normals = face_normals(&cube());
+        assert_close([0., 0., -1.], &normals[0]);
+        assert_close([0., 0., 1.], &normals[1]);
+        assert_close([1., 0., 0.], &normals[3]);
+    }
+
+    #[test]
+    fn normals_crease_angle() {
+        let mut obj = cube();
+        generate_normals(&mut obj, &NormalOptions::default());
+        assert_eq!(8, obj.normals.len());
+        let third = 1. / 3f64.sqrt();
+        let corner = obj.faces[1].verticies[2];
+        assert_eq!(6, corner.vertex);
+        assert_close([third, third, third], &obj.normals[corner.normal.unwrap()]);
+
+        let options = NormalOptions {
+            crease_angle: PI / 3.,
+            ..Default::default()
+        };
+        generate_normals(&mut obj, &options);
+        // Every corner keeps its face's normal
+        assert_eq!(24, obj.normals.len());
+        let corner = obj.faces[3].verticies[0];
+        assert_close([1., 0., 0.], &obj.normals[corner.normal.unwrap()]);
+        assert_eq!(12, obj.triangles.len());
+        assert_eq!(
+            obj.triangles[0].one.normal,
+            obj.faces[0].verticies[0].normal
+        );
+    }
+
+    #[test]
+    fn normals_smoothing_groups() {
+        let obj_str = CUBE
+            .replace("f 1 4 3 2", "s 1\nf 1 4 3 2")
+            .replace("f 2 3 7 6", "s 2\nf 2 3 7 6")
+            .replace("f 4 1 5 8", "s off\nf 4 1 5 8");
+        let mut obj = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        generate_normals(&mut obj, &NormalOptions::default());
+
+        // Faces 0 to 2 are smoothed together, 3 to 4 together, 5 is flat
+        let normal = |face: usize, corner: usize| {
+            obj.normals[obj.faces[face].verticies[corner].normal.unwrap()].clone()
+        };
+        let half = 1. / 2f64.sqrt();
+        // Vertex 1 in faces 0 and 2 and 5
+        assert_close([0., -half, -half], &normal(0, 0));
+        assert_close([-1., 0., 0.], &normal(5, 1));
+        // Vertex 7 in faces 3 and 4
+        assert_close([half, half, 0.], &normal(3, 2));
+
+        let options = NormalOptions {
+            smoothing_groups: false,
+            ..Default::default()
+        };
+        generate_normals(&mut obj, &options);
+        assert_eq!(8, obj.normals.len());
+    }
+
+    #[test]
+    fn normals_weighting() {
+        // A big floor triangle and a small wall triangle sharing vertex 1
+        let obj_str = "v 0 0 0\nv 4 0 0\nv 0 4 0\nv 0 0 1\nv 0 1 0\nf 1 2 3\nf 1 5 4\n";
+        let mut obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let normal = |obj: &ObjFile| obj.normals[obj.faces[0].verticies[0].normal.unwrap()].clone();
+
+        generate_normals(&mut obj, &NormalOptions::default());
+        // Both faces have a right angle there, so they count the same
+        let half = 1. / 2f64.sqrt();
+        assert_close([half, 0., half], &normal(&obj));
+
+        let options = NormalOptions {
+            weighting: NormalWeighting::Area,
+            ..Default::default()
+        };
+        generate_normals(&mut obj, &options);
+        // 8 vs 0.5 in area
+        let length = (16f64 * 16. + 1.).sqrt();
+        assert_close([1. / length, 0., 16. / length], &normal(&obj));
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    math::Vector3,
+    mesh::copy_face_ranges,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+// A new vertex as a weighted sum of old ones
+type Stencil = Vec<(usize, f64)>;
+
+fn apply(stencil: &Stencil, values: &[Vector3<f64>]) -> Vector3<f64> {
+    stencil
+        .iter()
+        .fold(Vector3::new([0., 0., 0.]), |sum, (index, weight)| {
+            sum + values[*index].clone() * *weight
+        })
+}
+
+fn average(indices: &[usize]) -> Stencil {
+    let weight = 1. / indices.len() as f64;
+    indices.iter().map(|&index| (index, weight)).collect()
+}
+
+fn scaled(stencil: &Stencil, scale: f64) -> impl Iterator<Item = (usize, f64)> + '_ {
+    stencil
+        .iter()
+        .map(move |(index, weight)| (*index, weight * scale))
+}
+
+// Edges are keyed by their sorted vertex indices
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+// Rules for a vertex on the open border of the mesh, shared by both schemes.
+// Corners where the border branches or ends stay put.
+fn boundary_stencil(vertex: usize, border: &[usize]) -> Stencil {
+    match border {
+        [a, b] => vec![(vertex, 0.75), (*a, 0.125), (*b, 0.125)],
+        _ => vec![(vertex, 1.)],
+    }
+}
+
+// Texture coordinates and normals live on corners, so they're interpolated linearly
+// between the corners of each face. Interpolated values are shared between faces that
+// use the same source indices.
+struct Attributes {
+    texture_coords: Vec<Vector3<f64>>,
+    normals: Vec<Vector3<f64>>,
+    texture_blends: HashMap<Vec<usize>, usize>,
+    normal_blends: HashMap<Vec<usize>, usize>,
+}
+
+fn blend(
+    list: &mut Vec<Vector3<f64>>,
+    blends: &mut HashMap<Vec<usize>, usize>,
+    mut sources: Vec<usize>,
+    unit: bool,
+) -> usize {
+    sources.sort_unstable();
+    if sources.windows(2).all(|pair| pair[0] == pair[1]) {
+        return sources[0];
+    }
+    *blends.entry(sources).or_insert_with_key(|sources| {
+        let mut value = apply(&average(sources), list);
+        if unit {
+            value = value.normalize();
+        }
+        list.push(value);
+        list.len() - 1
+    })
+}
+
+impl Attributes {
+    fn new(obj: &ObjFile) -> Self {
+        Attributes {
+            texture_coords: obj.texture_coords.clone(),
+            normals: obj.normals.clone(),
+            texture_blends: HashMap::new(),
+            normal_blends: HashMap::new(),
+        }
+    }
+
+    // A corner in between `corners`, with `vertex` as its position
+    fn corner(&mut self, vertex: usize, corners: &[FaceVertex]) -> FaceVertex {
+        let texture = corners
+            .iter()
+            .map(|corner| corner.texture)
+            .collect::<Option<Vec<usize>>>()
+            .map(|sources| {
+                blend(
+                    &mut self.texture_coords,
+                    &mut self.texture_blends,
+                    sources,
+                    false,
+                )
+            });
+        let normal = corners
+            .iter()
+            .map(|corner| corner.normal)
+            .collect::<Option<Vec<usize>>>()
+            .map(|sources| blend(&mut self.normals, &mut self.normal_blends, sources, true));
+        FaceVertex {
+            vertex,
+            texture,
+            normal,
+            tangent: None,
+        }
+    }
+}
+
+// Builds the refined mesh from the new verticies and faces
+fn finish(
+    obj: &ObjFile,
+    stencils: &[Stencil],
+    faces: Vec<Face>,
+    origins: &[usize],
+    attributes: Attributes,
+) -> ObjFile {
+    let mut res = ObjFile {
+        verticies: stencils
+            .iter()
+            .map(|stencil| apply(stencil, &obj.verticies))
+            .collect(),
+        texture_coords: attributes.texture_coords,
+        normals: attributes.normals,
+        faces,
+        // Old verticies keep their index so lines and points still line up
+        polylines: obj.polylines.clone(),
+        points: obj.points.clone(),
+        ..Default::default()
+    };
+    if !obj.vertex_colors.is_empty() {
+        res.vertex_colors = stencils
+            .iter()
+            .map(|stencil| apply(stencil, &obj.vertex_colors))
+            .collect();
+    }
+    copy_face_ranges(obj, &mut res, origins);
+    res.triangulate();
+    res
+}
+
+fn loop_level(obj: &ObjFile) -> ObjFile {
+    let triangles: Vec<[FaceVertex; 3]> = obj
+        .triangles
+        .iter()
+        .map(|t| [t.one, t.two, t.three])
+        .collect();
+
+    // The tips of the triangles on each side of every edge
+    let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for corners in &triangles {
+        for i in 0..3 {
+            let (a, b, tip) = (corners[i], corners[(i + 1) % 3], corners[(i + 2) % 3]);
+            edges
+                .entry(edge_key(a.vertex, b.vertex))
+                .or_default()
+                .push(tip.vertex);
+        }
+    }
+    let mut neighbours = vec![HashSet::new(); obj.verticies.len()];
+    let mut border = vec![Vec::new(); obj.verticies.len()];
+    for (&(a, b), tips) in &edges {
+        neighbours[a].insert(b);
+        neighbours[b].insert(a);
+        if tips.len() != 2 {
+            border[a].push(b);
+            border[b].push(a);
+        }
+    }
+
+    let mut stencils: Vec<Stencil> = (0..obj.verticies.len())
+        .map(|vertex| {
+            let ring = &neighbours[vertex];
+            if !border[vertex].is_empty() {
+                return boundary_stencil(vertex, &border[vertex]);
+            }
+            if ring.is_empty() {
+                return vec![(vertex, 1.)];
+            }
+            // Warren's weights
+            let n = ring.len() as f64;
+            let beta = if ring.len() == 3 {
+                3. / 16.
+            } else {
+                3. / (8. * n)
+            };
+            let mut stencil = vec![(vertex, 1. - n * beta)];
+            stencil.extend(ring.iter().map(|&other| (other, beta)));
+            stencil
+        })
+        .collect();
+
+    let mut edge_points = HashMap::new();
+    let mut keys: Vec<&(usize, usize)> = edges.keys().collect();
+    keys.sort_unstable();
+    for &(a, b) in keys {
+        let stencil = match edges[&(a, b)].as_slice() {
+            [c, d] => vec![(a, 0.375), (b, 0.375), (*c, 0.125), (*d, 0.125)],
+            _ => average(&[a, b]),
+        };
+        edge_points.insert((a, b), stencils.len());
+        stencils.push(stencil);
+    }
+
+    let mut attributes = Attributes::new(obj);
+    let mut faces = Vec::with_capacity(triangles.len() * 4);
+    let mut origins = Vec::with_capacity(triangles.len() * 4);
+    for (corners, triangle) in triangles.iter().zip(&obj.triangles) {
+        let middle: [FaceVertex; 3] = std::array::from_fn(|i| {
+            let (a, b) = (corners[i], corners[(i + 1) % 3]);
+            attributes.corner(edge_points[&edge_key(a.vertex, b.vertex)], &[a, b])
+        });
+        let [a, b, c] = *corners;
+        let [ab, bc, ca] = middle;
+        for verticies in [
+            vec![a, ab, ca],
+            vec![ab, b, bc],
+            vec![ca, bc, c],
+            vec![ab, bc, ca],
+        ] {
+            faces.push(Face { verticies });
+            origins.push(triangle.face);
+        }
+    }
+    finish(obj, &stencils, faces, &origins, attributes)
+}
+
+fn catmull_clark_level(obj: &ObjFile) -> ObjFile {
+    let vertex_count = obj.verticies.len();
+    // Faces that can't be split are dropped, `face_indices` maps back to the original
+    let (face_indices, faces): (Vec<usize>, Vec<&Face>) = obj
+        .faces
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| {
+            face.verticies.len() >= 3 && face.verticies.iter().all(|c| c.vertex < vertex_count)
+        })
+        .unzip();
+    let face_points: Vec<Stencil> = faces
+        .iter()
+        .map(|face| {
+            let indices: Vec<usize> = face.verticies.iter().map(|c| c.vertex).collect();
+            average(&indices)
+        })
+        .collect();
+
+    // The faces on each side of every edge
+    let mut edges: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    let mut touching = vec![Vec::new(); vertex_count];
+    for (index, face) in faces.iter().enumerate() {
+        let corners = &face.verticies;
+        for (i, corner) in corners.iter().enumerate() {
+            let next = corners[(i + 1) % corners.len()];
+            edges
+                .entry(edge_key(corner.vertex, next.vertex))
+                .or_default()
+                .push(index);
+            touching[corner.vertex].push(index);
+        }
+    }
+    let mut ring = vec![Vec::new(); vertex_count];
+    let mut border = vec![Vec::new(); vertex_count];
+    for (&(a, b), sides) in &edges {
+        ring[a].push(b);
+        ring[b].push(a);
+        if sides.len() != 2 {
+            border[a].push(b);
+            border[b].push(a);
+        }
+    }
+
+    let mut stencils: Vec<Stencil> = (0..vertex_count)
+        .map(|vertex| {
+            if !border[vertex].is_empty() {
+                return boundary_stencil(vertex, &border[vertex]);
+            }
+            if ring[vertex].is_empty() {
+                return vec![(vertex, 1.)];
+            }
+            // (F + 2R + (n - 3) P) / n, with F the average face point and R the average
+            // edge midpoint
+            let n = ring[vertex].len() as f64;
+            let faces = touching[vertex].len() as f64;
+            let mut stencil = vec![(vertex, (n - 3.) / n + 1. / n)];
+            for &face in &touching[vertex] {
+                stencil.extend(scaled(&face_points[face], 1. / (faces * n)));
+            }
+            stencil.extend(ring[vertex].iter().map(|&other| (other, 1. / (n * n))));
+            stencil
+        })
+        .collect();
+
+    let mut edge_points = HashMap::new();
+    let mut keys: Vec<&(usize, usize)> = edges.keys().collect();
+    keys.sort_unstable();
+    for &(a, b) in keys {
+        let stencil = match edges[&(a, b)].as_slice() {
+            [f, g] => {
+                let mut stencil = vec![(a, 0.25), (b, 0.25)];
+                stencil.extend(scaled(&face_points[*f], 0.25));
+                stencil.extend(scaled(&face_points[*g], 0.25));
+                stencil
+            }
+            _ => average(&[a, b]),
+        };
+        edge_points.insert((a, b), stencils.len());
+        stencils.push(stencil);
+    }
+    let first_face_point = stencils.len();
+    stencils.extend(face_points);
+
+    let mut attributes = Attributes::new(obj);
+    let mut quads = Vec::new();
+    let mut origins = Vec::new();
+    for (index, face) in faces.iter().enumerate() {
+        let corners = &face.verticies;
+        let count = corners.len();
+        let center = attributes.corner(first_face_point + index, corners);
+        let middle: Vec<FaceVertex> = (0..count)
+            .map(|i| {
+                let (a, b) = (corners[i], corners[(i + 1) % count]);
+                attributes.corner(edge_points[&edge_key(a.vertex, b.vertex)], &[a, b])
+            })
+            .collect();
+        for i in 0..count {
+            let verticies = vec![
+                corners[i],
+                middle[i],
+                center,
+                middle[(i + count - 1) % count],
+            ];
+            quads.push(Face { verticies });
+            origins.push(face_indices[index]);
+        }
+    }
+    finish(obj, &stencils, quads, &origins, attributes)
+}
+
+// Loop subdivision of `obj.triangles`, every level splits each triangle in four.
+// Corners of the border follow the cubic B-spline rule so open edges stay smooth.
+pub fn loop_subdivide(obj: &ObjFile, levels: usize) -> ObjFile {
+    if levels == 0 {
+        return obj.clone();
+    }
+    let mut res = loop_level(obj);
+    for _ in 1..levels {
+        res = loop_level(&res);
+    }
+    res
+}
+
+// Catmull-Clark subdivision of `obj.faces`, every level splits an n-gon into n quads
+pub fn catmull_clark(obj: &ObjFile, levels: usize) -> ObjFile {
+    if levels == 0 {
+        return obj.clone();
+    }
+    let mut res = catmull_clark_level(obj);
+    for _ in 1..levels {
+        res = catmull_clark_level(&res);
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        mesh::subdivide::{catmull_clark, loop_subdivide},
+        obj::{MaterialRange, ObjParseOptions, mtl::Material, parse_obj_str},
+    };
+
+    const CUBE: &str =
+        "v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+g cube
+f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8
+";
+
+    fn assert_close(expected: Vector3<f64>, actual: &Vector3<f64>) {
+        let difference = expected.clone() - actual.clone();
+        assert!(
+            difference.dot(&difference) < 1e-18,
+            "{} != {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn subdivide_catmull_clark_cube() {
+        let obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        let res = catmull_clark(&obj, 1);
+        assert_eq!(8 + 12 + 6, res.verticies.len());
+        assert_eq!(24, res.faces.len());
+        assert!(res.faces.iter().all(|face| face.verticies.len() == 4));
+        // (F + 2R) / 3 with F = 1/3 and R = 2/3 on each axis
+        assert_close(Vector3::new([5., 5., 5.]) / 9., &res.verticies[6]);
+        // Face points stay in the middle of the faces
+        assert!(res.verticies.contains(&Vector3::new([0., 0., 1.])));
+        assert_eq!(0..24, res.groups[0].faces);
+
+        let res = catmull_clark(&obj, 2);
+        assert_eq!(96, res.faces.len());
+    }
+
+    #[test]
+    fn subdivide_catmull_clark_open_quad() {
+        let obj_str = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nf 1/1 2/2 3/3 4/4\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let res = catmull_clark(&obj, 1);
+        assert_eq!(9, res.verticies.len());
+        assert_eq!(4, res.faces.len());
+        // Border rule 3/4 P + 1/8 of each border neighbour
+        assert_close(Vector3::new([0.25, 0.25, 0.]), &res.verticies[0]);
+        assert!(res.verticies.contains(&Vector3::new([1., 0., 0.])));
+        assert!(res.verticies.contains(&Vector3::new([1., 1., 0.])));
+        for face in &res.faces {
+            let center = face.verticies[2];
+            assert_eq!(Vector3::new([1., 1., 0.]), res.verticies[center.vertex]);
+            let uv = &res.texture_coords[center.texture.unwrap()];
+            assert_eq!(Vector3::new([0.5, 0.5, 0.]), *uv);
+        }
+        // Edge corners share their texture coordinates between the faces
+        assert_eq!(4 + 4 + 1, res.texture_coords.len());
+    }
+
+    #[test]
+    fn subdivide_loop_tetrahedron() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nf 1 3 2\nf 1 2 4\nf 2 3 4\nf 1 4 3\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let res = loop_subdivide(&obj, 1);
+        assert_eq!(4 + 6, res.verticies.len());
+        assert_eq!(16, res.triangles.len());
+        // Valence 3, 1 - 3 * 3/16 of the vertex and 3/16 of each neighbour
+        assert_close(Vector3::new([3., 3., 3.]) / 16., &res.verticies[0]);
+        // Edge points take 3/8 of the ends and 1/8 of the tips
+        assert!(res.verticies.contains(&Vector3::new([0.375, 0.125, 0.125])));
+
+        let res = loop_subdivide(&obj, 3);
+        assert_eq!(4 * 64, res.triangles.len());
+    }
+
+    #[test]
+    fn subdivide_loop_open_triangle() {
+        let obj_str =
+            "v 0 0 0\nv 4 0 0\nv 0 4 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1\n";
+        let obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let res = loop_subdivide(&obj, 1);
+        assert_eq!(6, res.verticies.len());
+        assert_eq!(4, res.faces.len());
+        assert_eq!(Vector3::new([0.5, 0.5, 0.]), res.verticies[0]);
+        assert!(res.verticies.contains(&Vector3::new([2., 2., 0.])));
+        let middle = res.faces[3].verticies[0];
+        assert_eq!(Vector3::new([2., 0., 0.]), res.verticies[middle.vertex]);
+        assert_eq!(
+            Vector3::new([0.5, 0., 0.]),
+            res.texture_coords[middle.texture.unwrap()]
+        );
+        // The normals all agree so no new ones are made
+        assert_eq!(1, res.normals.len());
+        assert!(
+            res.faces
+                .iter()
+                .flat_map(|face| &face.verticies)
+                .all(|corner| corner.normal == Some(0))
+        );
+    }
+
+    #[test]
+    fn subdivide_zero_levels() {
+        let obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        for res in [catmull_clark(&obj, 0), loop_subdivide(&obj, 0)] {
+            assert_eq!(obj.verticies, res.verticies);
+            assert_eq!(obj.faces, res.faces);
+            assert_eq!(obj.triangles.len(), res.triangles.len());
+            assert_eq!(obj.groups, res.groups);
+        }
+    }
+
+    #[test]
+    fn subdivide_keeps_materials() {
+        let mut obj = parse_obj_str(CUBE, &ObjParseOptions::default()).unwrap();
+        obj.materials.push(Material::new("red"));
+        obj.material_ranges.push(MaterialRange {
+            material: 0,
+            faces: 1..2,
+        });
+        let res = catmull_clark(&obj, 1);
+        // Each quad of the cube becomes four
+        assert!(res.face_material(3).is_none());
+        assert_eq!("red", res.face_material(4).unwrap().name);
+        assert_eq!("red", res.face_material(7).unwrap().name);
+        assert!(res.face_material(8).is_none());
+
+        let res = loop_subdivide(&obj, 1);
+        // Two triangles per quad, each split in four
+        assert!(res.face_material(7).is_none());
+        assert_eq!("red", res.face_material(8).unwrap().name);
+        assert_eq!("red", res.face_material(15).unwrap().name);
+        assert!(res.face_material(16).is_none());
+    }
+}
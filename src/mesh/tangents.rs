@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::{Vector3, Vector4},
+    mesh::normals::face_normals,
+    obj::ObjFile,
+    types::FaceVertex,
+};
+
+const EPSILON: f64 = 1e-20;
+
+// Removes the part of `vector` along the unit `normal`
+fn project(vector: &Vector3<f64>, normal: &Vector3<f64>) -> Vector3<f64> {
+    (vector.clone() - normal.clone() * normal.dot(vector)).normalize()
+}
+
+// Corners are grouped like MikkTSpace does: same position, uv and normal, and the same
+// uv orientation so mirrored halves of a model don't blend their tangents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CornerKey {
+    vertex: usize,
+    texture: usize,
+    // Face index stands in for corners without a normal, they use the face normal
+    normal: Result<usize, usize>,
+    flipped: bool,
+}
+
+struct Corner {
+    key: CornerKey,
+    normal: Vector3<f64>,
+}
+
+// Fills `obj.tangents` and points every face corner with a texture coordinate at one.
+// Normals should be there first, see generate_normals. Corners without one use the face normal.
+//
+// Follows MikkTSpace: tangents come from the uv gradient of each triangle, are projected onto
+// the vertex normal and summed weighted by the corner angle.
+pub fn generate_tangents(obj: &mut ObjFile) {
+    let unit_normals = face_normals(obj);
+    let corner_of = |face: usize, face_vertex: &FaceVertex, flipped: bool| -> Option<Corner> {
+        let texture = face_vertex
+            .texture
+            .filter(|&t| t < obj.texture_coords.len())?;
+        let normal = face_vertex
+            .normal
+            .and_then(|n| obj.normals.get(n).map(|normal| (n, normal)));
+        Some(Corner {
+            key: CornerKey {
+                vertex: face_vertex.vertex,
+                texture,
+                normal: normal.map(|(n, _)| n).ok_or(face),
+                flipped,
+            },
+            normal: normal
+                .map_or(unit_normals[face].clone(), |(_, n)| n.clone())
+                .normalize(),
+        })
+    };
+
+    // Signed uv area of every triangle, and of every face as the sum of its triangles
+    let uv_area = |corners: [&FaceVertex; 3]| -> Option<f64> {
+        let uvs = corners
+            .iter()
+            .map(|corner| corner.texture.and_then(|t| obj.texture_coords.get(t)))
+            .collect::<Option<Vec<_>>>()?;
+        let (du1, dv1) = (uvs[1].x() - uvs[0].x(), uvs[1].y() - uvs[0].y());
+        let (du2, dv2) = (uvs[2].x() - uvs[0].x(), uvs[2].y() - uvs[0].y());
+        Some(du1 * dv2 - du2 * dv1)
+    };
+    let mut face_areas = vec![0.; obj.faces.len()];
+    for triangle in &obj.triangles {
+        if let Some(area) = uv_area([&triangle.one, &triangle.two, &triangle.three]) {
+            face_areas[triangle.face] += area;
+        }
+    }
+
+    let mut sums: HashMap<CornerKey, Vector3<f64>> = HashMap::new();
+    for triangle in &obj.triangles {
+        let corners = [&triangle.one, &triangle.two, &triangle.three];
+        let Some(area) = uv_area(corners) else {
+            continue;
+        };
+        let positions = corners
+            .iter()
+            .map(|corner| obj.verticies.get(corner.vertex))
+            .collect::<Option<Vec<_>>>();
+        // Triangles collapsed in uv space have no direction to give
+        let Some(positions) = positions.filter(|_| area.abs() > EPSILON) else {
+            continue;
+        };
+        let uvs: Vec<&Vector3<f64>> = corners
+            .iter()
+            .map(|corner| &obj.texture_coords[corner.texture.unwrap_or_default()])
+            .collect();
+        let edge_one = positions[1].clone() - positions[0].clone();
+        let edge_two = positions[2].clone() - positions[0].clone();
+        let (dv1, dv2) = (uvs[1].y() - uvs[0].y(), uvs[2].y() - uvs[0].y());
+        let tangent = (edge_one * dv2 - edge_two * dv1) / area;
+
+        let flipped = face_areas[triangle.face] < 0.;
+        for (index, face_vertex) in corners.iter().enumerate() {
+            let Some(corner) = corner_of(triangle.face, face_vertex, flipped) else {
+                continue;
+            };
+            let prev = positions[(index + 2) % 3].clone() - positions[index].clone();
+            let next = positions[(index + 1) % 3].clone() - positions[index].clone();
+            let angle = prev
+                .normalize()
+                .dot(&next.normalize())
+                .clamp(-1., 1.)
+                .acos();
+            let projected = project(&tangent, &corner.normal);
+            let sum = sums
+                .entry(corner.key)
+                .or_insert_with(|| Vector3::new([0., 0., 0.]));
+            *sum = sum.clone() + projected * angle;
+        }
+    }
+
+    let mut tangents: Vec<Vector4<f64>> = Vec::new();
+    let mut indices: HashMap<CornerKey, usize> = HashMap::new();
+    for (face, face_area) in face_areas.iter().enumerate() {
+        let flipped = *face_area < 0.;
+        for corner_index in 0..obj.faces[face].verticies.len() {
+            let face_vertex = obj.faces[face].verticies[corner_index];
+            let tangent = corner_of(face, &face_vertex, flipped).map(|corner| {
+                *indices.entry(corner.key).or_insert_with(|| {
+                    let sum = sums
+                        .get(&corner.key)
+                        .cloned()
+                        .unwrap_or_else(|| Vector3::new([0., 0., 0.]));
+                    let mut tangent = project(&sum, &corner.normal);
+                    // Nothing to go on, any direction in the tangent plane will do
+                    if tangent.dot(&tangent) == 0. {
+                        tangent = any_perpendicular(&corner.normal);
+                    }
+                    let w = if flipped { -1. } else { 1. };
+                    tangents.push(Vector4::new([tangent.x(), tangent.y(), tangent.z(), w]));
+                    tangents.len() - 1
+                })
+            });
+            obj.faces[face].verticies[corner_index].tangent = tangent;
+        }
+    }
+    obj.tangents = tangents;
+    obj.triangulate();
+}
+
+fn any_perpendicular(normal: &Vector3<f64>) -> Vector3<f64> {
+    let axis = if normal.x().abs() < 0.9 {
+        Vector3::new([1., 0., 0.])
+    } else {
+        Vector3::new([0., 1., 0.])
+    };
+    normal.cross(&axis).normalize()
+}
+
+pub fn bitangent(normal: &Vector3<f64>, tangent: &Vector4<f64>) -> Vector3<f64> {
+    normal.cross(&tangent.xyz()) * tangent.w()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        math::Vector3,
+        mesh::tangents::{bitangent, generate_tangents},
+        obj::{ObjParseOptions, parse_obj_file, parse_obj_str},
+    };
+
+    fn assert_close(expected: [f64; 3], res: &Vector3<f64>) {
+        for (a, b) in expected.iter().zip(res.get_data()) {
+            assert!((a - b).abs() < 1e-9, "{:?} != {}", expected, res);
+        }
+    }
+
+    #[test]
+    fn tangents_follow_uvs() {
+        // A quad in the xy plane, u along -y and v along +x
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0
+vt 0 0\nvt 0 1\nvt -1 1\nvt -1 0\nvn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1\n";
+        let mut obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        generate_tangents(&mut obj);
+        // One per corner, the uvs all differ
+        assert_eq!(4, obj.tangents.len());
+        for tangent in &obj.tangents {
+            assert_close([0., -1., 0.], &tangent.xyz());
+        }
+        let tangent = &obj.tangents[0];
+        assert_eq!(1., tangent.w());
+        assert_close([1., 0., 0.], &bitangent(&obj.normals[0], tangent));
+        assert_eq!(Some(0), obj.triangles[0].one.tangent);
+    }
+
+    #[test]
+    fn tangents_mirrored_uvs() {
+        // Two triangles sharing an edge, the second with mirrored uvs
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv -1 0 0
+vt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1
+f 1/1/1 2/2/1 3/3/1\nf 1/1/1 3/3/1 4/2/1\n";
+        let mut obj = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        generate_tangents(&mut obj);
+        let tangent = |face: usize, corner: usize| {
+            obj.tangents[obj.faces[face].verticies[corner].tangent.unwrap()].clone()
+        };
+        // The shared corners are split by orientation
+        assert_eq!(6, obj.tangents.len());
+        assert_close([1., 0., 0.], &tangent(0, 0).xyz());
+        assert_eq!(1., tangent(0, 0).w());
+        assert_close([-1., 0., 0.], &tangent(1, 0).xyz());
+        assert_eq!(-1., tangent(1, 0).w());
+        // The bitangent still points along +v on both sides
+        assert_close([0., 1., 0.], &bitangent(&obj.normals[0], &tangent(1, 0)));
+    }
+
+    #[test]
+    fn tangents_diablo() {
+        let mut obj = parse_obj_file(Path::new("./assets/diablo.obj")).unwrap();
+        generate_tangents(&mut obj);
+        assert!(!obj.tangents.is_empty());
+        for face in &obj.faces {
+            for corner in &face.verticies {
+                let tangent = &obj.tangents[corner.tangent.unwrap()];
+                let normal = &obj.normals[corner.normal.unwrap()];
+                let xyz = tangent.xyz();
+                assert!((xyz.dot(&xyz) - 1.).abs() < 1e-9);
+                // Perpendicular to the normal
+                assert!(xyz.dot(normal).abs() < 1e-2);
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::math::Vector3;
+
+const EPSILON: f64 = 1e-12;
+
+// Newell's method. Gives a usable normal for non-planar polygons too.
+pub(crate) fn polygon_normal(points: &[Vector3<f64>]) -> Vector3<f64> {
+    let mut normal = [0., 0., 0.];
+    for (i, current) in points.iter().enumerate() {
+        let next = &points[(i + 1) % points.len()];
+        normal[0] += (current.y() - next.y()) * (current.z() + next.z());
+        normal[1] += (current.z() - next.z()) * (current.x() + next.x());
+        normal[2] += (current.x() - next.x()) * (current.y() + next.y());
+    }
+    Vector3::new(normal)
+}
+
+fn is_convex(points: &[Vector3<f64>], normal: &Vector3<f64>) -> bool {
+    let n = points.len();
+    (0..n).all(|i| {
+        let prev = &points[(i + n - 1) % n];
+        let current = &points[i];
+        let next = &points[(i + 1) % n];
+        let turn = (current.clone() - prev.clone()).cross(&(next.clone() - current.clone()));
+        turn.dot(normal) >= -EPSILON
+    })
+}
+
+// Drops the dominant axis of the normal, keeping the winding counter-clockwise
+fn project_to_plane(points: &[Vector3<f64>], normal: &Vector3<f64>) -> Vec<[f64; 2]> {
+    let [nx, ny, nz] = normal.get_data().map(f64::abs);
+    let (u, v, flip) = if nx >= ny && nx >= nz {
+        (1, 2, normal.x() < 0.)
+    } else if ny >= nz {
+        (2, 0, normal.y() < 0.)
+    } else {
+        (0, 1, normal.z() < 0.)
+    };
+    points
+        .iter()
+        .map(|p| if flip { [p[v], p[u]] } else { [p[u], p[v]] })
+        .collect()
+}
+
+fn orient_2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn point_in_triangle(p: [f64; 2], a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> bool {
+    orient_2d(a, b, p) >= 0. && orient_2d(b, c, p) >= 0. && orient_2d(c, a, p) >= 0.
+}
+
+fn ear_clip(points: &[[f64; 2]]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let is_ear = |i: usize| {
+            let (prev, current, next) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (a, b, c) = (points[prev], points[current], points[next]);
+            if orient_2d(a, b, c) <= EPSILON {
+                return false;
+            }
+            !remaining
+                .iter()
+                .filter(|&&other| other != prev && other != current && other != next)
+                .any(|&other| point_in_triangle(points[other], a, b, c))
+        };
+        // Self intersecting or degenerate polygons might not have an ear.
+        // Clipping the first corner keeps things moving instead of looping forever.
+        let ear = (0..n).find(|&i| is_ear(i)).unwrap_or(0);
+        triangles.push([
+            remaining[(ear + n - 1) % n],
+            remaining[ear],
+            remaining[(ear + 1) % n],
+        ]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+// Splits a polygon into triangles.
+// Convex polygons are fanned, concave or non-planar ones go through ear clipping.
+// Returned triples are positions within `points`, not vertex indexes.
+pub fn triangulate(points: &[Vector3<f64>]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    let normal = polygon_normal(points);
+    if normal.dot(&normal) <= EPSILON || is_convex(points, &normal) {
+        return (1..n - 1).map(|i| [0, i, i + 1]).collect();
+    }
+
+    ear_clip(&project_to_plane(points, &normal))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::Vector3;
+    use crate::mesh::triangulate;
+
+    fn area_2d(points: &[Vector3<f64>], triangle: [usize; 3]) -> f64 {
+        let [a, b, c] = triangle.map(|i| &points[i]);
+        0.5 * ((b.x() - a.x()) * (c.y() - a.y()) - (b.y() - a.y()) * (c.x() - a.x()))
+    }
+
+    #[test]
+    fn triangulate_quad_fan() {
+        let points = [
+            Vector3::new([0., 0., 0.]),
+            Vector3::new([1., 0., 0.]),
+            Vector3::new([1., 1., 0.]),
+            Vector3::new([0., 1., 0.]),
+        ];
+
+        let res = triangulate(&points);
+        assert_eq!(vec![[0, 1, 2], [0, 2, 3]], res);
+    }
+
+    #[test]
+    fn triangulate_concave_ear_clip() {
+        // L shape, the reflex corner is at index 3
+        let points = [
+            Vector3::new([0., 0., 0.]),
+            Vector3::new([2., 0., 0.]),
+            Vector3::new([2., 1., 0.]),
+            Vector3::new([1., 1., 0.]),
+            Vector3::new([1., 2., 0.]),
+            Vector3::new([0., 2., 0.]),
+        ];
+
+        let res = triangulate(&points);
+        assert_eq!(4, res.len());
+        for triangle in &res {
+            assert!(area_2d(&points, *triangle) > 0.);
+        }
+        let total: f64 = res.iter().map(|t| area_2d(&points, *t)).sum();
+        assert!((total - 3.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn triangulate_too_few_points() {
+        let points = [Vector3::new([0., 0., 0.]), Vector3::new([1., 0., 0.])];
+        assert!(triangulate(&points).is_empty());
+    }
+}
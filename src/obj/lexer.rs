@@ -0,0 +1,158 @@
+// Lexical rules shared by OBJ and MTL files:
+// - '#' starts a comment that runs to the end of the line
+// - a '\' at the end of a line joins it with the next one
+// - tokens are separated by any run of spaces or tabs
+use std::borrow::Cow;
+
+pub fn strip_comment(line: &str) -> &str {
+    line.split_once('#').map_or(line, |(content, _)| content)
+}
+
+pub fn tokenize(line: &str) -> Vec<&str> {
+    line.split_ascii_whitespace().collect()
+}
+
+// The line without its comment and continuation marker.
+// Second value is true when the statement carries on to the next line.
+pub fn split_continuation(line: &str) -> (&str, bool) {
+    let content = strip_comment(line).trim_end();
+    match content.strip_suffix('\\') {
+        Some(content) => (content, true),
+        None => (content, false),
+    }
+}
+
+// Joins continued lines into whole statements.
+// Yields the number of the first physical line along with the statement text.
+pub struct LogicalLines<'a> {
+    lines: std::str::Lines<'a>,
+    line_number: usize,
+}
+
+impl<'a> LogicalLines<'a> {
+    pub fn new(str: &'a str) -> Self {
+        LogicalLines {
+            lines: str.lines(),
+            line_number: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for LogicalLines<'a> {
+    type Item = (usize, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.lines.next()?;
+        self.line_number += 1;
+        let start = self.line_number;
+        let (content, mut continued) = split_continuation(first);
+        if !continued {
+            return Some((start, Cow::Borrowed(content)));
+        }
+
+        let mut joined = content.to_string();
+        while continued && let Some(line) = self.lines.next() {
+            self.line_number += 1;
+            let (content, next_continued) = split_continuation(line);
+            joined.push(' ');
+            joined.push_str(content);
+            continued = next_continued;
+        }
+        Some((start, Cow::Owned(joined)))
+    }
+}
+
+// Numbers like "1", "-0.5", ".25", "1e-5" and "+3.2E+02".
+// Stricter than str::parse, which also takes "inf" and "NaN".
+pub fn parse_real(token: &str) -> Option<f64> {
+    let bytes = token.as_bytes();
+    let mut pos = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        pos += 1;
+    }
+    let digits = |pos: &mut usize| {
+        let start = *pos;
+        while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+            *pos += 1;
+        }
+        *pos - start
+    };
+    let mut mantissa_digits = digits(&mut pos);
+    if bytes.get(pos) == Some(&b'.') {
+        pos += 1;
+        mantissa_digits += digits(&mut pos);
+    }
+    if mantissa_digits == 0 {
+        return None;
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        pos += 1;
+        if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+            pos += 1;
+        }
+        if digits(&mut pos) == 0 {
+            return None;
+        }
+    }
+    if pos != bytes.len() {
+        return None;
+    }
+    token.parse::<f64>().ok()
+}
+
+pub fn parse_integer(token: &str) -> Option<isize> {
+    let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+    if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse::<isize>().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::obj::lexer::{LogicalLines, parse_integer, parse_real, tokenize};
+
+    #[test]
+    fn lexer_whitespace_and_comments() {
+        let tokens = tokenize("v\t1.0  2.0 \t 3.0   ");
+        assert_eq!(vec!["v", "1.0", "2.0", "3.0"], tokens);
+
+        let lines: Vec<(usize, String)> = LogicalLines::new("# header\nv 1 2 3 # trailing\r\n")
+            .map(|(line, content)| (line, content.into_owned()))
+            .collect();
+        assert_eq!(vec![(1, "".to_string()), (2, "v 1 2 3".to_string())], lines);
+    }
+
+    #[test]
+    fn lexer_line_continuation() {
+        let obj_str = "f 1 2 \\\n  3 4\\\n 5\nv 0 0 0\n";
+        let mut lines = LogicalLines::new(obj_str);
+
+        let (line, content) = lines.next().unwrap();
+        assert_eq!(1, line);
+        assert_eq!(vec!["f", "1", "2", "3", "4", "5"], tokenize(&content));
+
+        let (line, content) = lines.next().unwrap();
+        assert_eq!(4, line);
+        assert_eq!("v 0 0 0", content);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn lexer_numbers() {
+        assert_eq!(Some(1.5e-3), parse_real("1.5e-3"));
+        assert_eq!(Some(-320.), parse_real("-3.2E+02"));
+        assert_eq!(Some(0.25), parse_real(".25"));
+        assert_eq!(Some(2.), parse_real("+2."));
+        assert_eq!(None, parse_real("inf"));
+        assert_eq!(None, parse_real("NaN"));
+        assert_eq!(None, parse_real("1e"));
+        assert_eq!(None, parse_real("."));
+        assert_eq!(None, parse_real("1.0f"));
+
+        assert_eq!(Some(-3), parse_integer("-3"));
+        assert_eq!(Some(7), parse_integer("+7"));
+        assert_eq!(None, parse_integer("1.0"));
+        assert_eq!(None, parse_integer("-"));
+    }
+}
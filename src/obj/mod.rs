@@ -0,0 +1,151 @@
+pub mod lexer;
+pub mod mtl;
+pub mod parallel;
+pub mod parser;
+pub mod writer;
+
+use crate::{
+    math::{Vector3, Vector4},
+    mesh::triangulate,
+    types::{Face, Polyline, TriangleFace},
+};
+use std::ops::Range;
+
+use mtl::Material;
+pub use parallel::{parse_obj_file_parallel, parse_obj_str_parallel};
+pub use parser::{
+    ObjParseError, ObjParseOptions, ParseMode, ParseProgress, ProgressCallback, parse_obj_file,
+    parse_obj_file_with_options, parse_obj_reader, parse_obj_str,
+};
+pub use writer::{ObjWriteOptions, write_mtl, write_obj, write_obj_file};
+
+// Faces in `faces` drawn with `materials[material]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialRange {
+    pub material: usize,
+    pub faces: Range<usize>,
+}
+
+// A named run of faces, from an "o" or "g" statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubMesh {
+    pub name: String,
+    pub faces: Range<usize>,
+}
+
+// Faces sharing a non-zero "s" id are smoothed together
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothingGroup {
+    pub id: u32,
+    pub faces: Range<usize>,
+}
+
+// Picks which objects and groups get drawn, matched by name
+#[derive(Debug, Clone, Default)]
+pub enum SubMeshFilter {
+    #[default]
+    All,
+    Only(Vec<String>),
+    Skip(Vec<String>),
+}
+
+#[derive(Clone, Default)]
+pub struct ObjFile {
+    pub verticies: Vec<Vector3<f64>>,
+    // RGB in 0..1, one per vertex. Empty when the file has no vertex colors.
+    pub vertex_colors: Vec<Vector3<f64>>,
+    // u, v and w. Missing components are 0.
+    pub texture_coords: Vec<Vector3<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    // xyz along increasing u, w is +1 or -1 so the bitangent is cross(normal, xyz) * w
+    pub tangents: Vec<Vector4<f64>>,
+    pub faces: Vec<Face>,
+    pub triangles: Vec<TriangleFace>,
+    pub polylines: Vec<Polyline>,
+    // Vertex indexes from "p" statements
+    pub points: Vec<usize>,
+    pub materials: Vec<Material>,
+    pub material_ranges: Vec<MaterialRange>,
+    pub objects: Vec<SubMesh>,
+    // A face can be in several groups, "g arm left" puts it in both
+    pub groups: Vec<SubMesh>,
+    pub smoothing_groups: Vec<SmoothingGroup>,
+    // Problems skipped over while parsing in lenient mode
+    pub warnings: Vec<ObjParseError>,
+}
+
+impl ObjFile {
+    // Keeps `vertex_colors` either empty or in step with `verticies`.
+    // Uncolored verticies in a colored mesh are white.
+    pub fn push_vertex(&mut self, vertex: Vector3<f64>, color: Option<Vector3<f64>>) {
+        let white = || Vector3::new([1., 1., 1.]);
+        let colored = color.is_some() || !self.vertex_colors.is_empty();
+        if colored && self.vertex_colors.is_empty() {
+            self.vertex_colors = vec![white(); self.verticies.len()];
+        }
+        if colored {
+            self.vertex_colors.push(color.unwrap_or_else(white));
+        }
+        self.verticies.push(vertex);
+    }
+
+    pub fn face_material(&self, face: usize) -> Option<&Material> {
+        self.material_ranges
+            .iter()
+            .find(|range| range.faces.contains(&face))
+            .and_then(|range| self.materials.get(range.material))
+    }
+
+    // 0 when the face isn't smoothed
+    pub fn face_smoothing_group(&self, face: usize) -> u32 {
+        self.smoothing_groups
+            .iter()
+            .find(|group| group.faces.contains(&face))
+            .map_or(0, |group| group.id)
+    }
+
+    // One entry per face, true when the filter lets the face through
+    pub fn face_mask(&self, filter: &SubMeshFilter) -> Vec<bool> {
+        let mark = |names: &[String]| {
+            let mut mask = vec![false; self.faces.len()];
+            for sub_mesh in self.objects.iter().chain(self.groups.iter()) {
+                if names.contains(&sub_mesh.name) {
+                    for face in sub_mesh.faces.clone() {
+                        mask[face] = true;
+                    }
+                }
+            }
+            mask
+        };
+        match filter {
+            SubMeshFilter::All => vec![true; self.faces.len()],
+            SubMeshFilter::Only(names) => mark(names),
+            SubMeshFilter::Skip(names) => mark(names).into_iter().map(|skip| !skip).collect(),
+        }
+    }
+
+    // Rebuilds `triangles` from `faces`
+    pub fn triangulate(&mut self) {
+        self.triangles.clear();
+        for (face_index, face) in self.faces.iter().enumerate() {
+            let points = face
+                .verticies
+                .iter()
+                .map(|corner| self.verticies.get(corner.vertex))
+                .collect::<Option<Vec<&Vector3<f64>>>>();
+            // Faces pointing at missing verticies can't be triangulated
+            let Some(points) = points else {
+                continue;
+            };
+            let points: Vec<Vector3<f64>> = points.into_iter().cloned().collect();
+            for [one, two, three] in triangulate(&points) {
+                self.triangles.push(TriangleFace {
+                    one: face.verticies[one],
+                    two: face.verticies[two],
+                    three: face.verticies[three],
+                    face: face_index,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    math::Vector3,
+    obj::lexer::{LogicalLines, parse_real, tokenize},
+    tga::{Image, RGBA},
+};
+use anyhow::Result;
+
+use std::{
+    fs::{self},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone)]
+pub struct Texture {
+    pub path: PathBuf,
+    // None when the file is missing or in a format we can't read yet
+    pub image: Option<Image<RGBA>>,
+}
+
+impl Texture {
+    pub(crate) fn load(path: PathBuf) -> Self {
+        let is_tga = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tga"));
+        let image = path
+            .to_str()
+            .filter(|_| is_tga)
+            .and_then(|filename| Image::<RGBA>::read_from_file(filename).ok());
+        Texture { path, image }
+    }
+}
+
+#[derive(Clone)]
+pub struct Material {
+    pub name: String,
+    // Ka
+    pub ambient: Vector3<f64>,
+    // Kd
+    pub diffuse: Vector3<f64>,
+    // Ks
+    pub specular: Vector3<f64>,
+    // Ns
+    pub shininess: f64,
+    // d, or 1 - Tr. 1 is fully opaque.
+    pub dissolve: f64,
+    // illum
+    pub illumination: u32,
+    // map_Kd
+    pub diffuse_map: Option<Texture>,
+    // map_Bump or bump
+    pub bump_map: Option<Texture>,
+    // map_Ks
+    pub specular_map: Option<Texture>,
+    // Pm, 0 is dielectric and 1 is metal
+    pub metallic: f64,
+    // Pr
+    pub roughness: f64,
+    // Metalness in the blue channel and roughness in green, like glTF. No MTL statement.
+    pub metallic_roughness_map: Option<Texture>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_string(),
+            ambient: Vector3::new([0., 0., 0.]),
+            diffuse: Vector3::new([1., 1., 1.]),
+            specular: Vector3::new([0., 0., 0.]),
+            shininess: 0.,
+            dissolve: 1.,
+            illumination: 2,
+            diffuse_map: None,
+            bump_map: None,
+            specular_map: None,
+            metallic: 0.,
+            roughness: 1.,
+            metallic_roughness_map: None,
+        }
+    }
+}
+
+fn parse_color(values: &[&str]) -> Option<Vector3<f64>> {
+    match values {
+        // "Kd 0.5" is shorthand for "Kd 0.5 0.5 0.5"
+        [value] => {
+            let value = parse_real(value)?;
+            Some(Vector3::new([value, value, value]))
+        }
+        [r, g, b, ..] => Some(Vector3::new([
+            parse_real(r)?,
+            parse_real(g)?,
+            parse_real(b)?,
+        ])),
+        _ => None,
+    }
+}
+
+fn parse_scalar(values: &[&str]) -> Option<f64> {
+    parse_real(values.first()?)
+}
+
+// Map statements can carry options like "-bm 0.5" or "-s 1 1 1" before the file name.
+// Whatever follows the options is the file name, spaces included.
+fn parse_texture(values: &[&str], base_dir: &Path) -> Option<Texture> {
+    let mut rest = values;
+    while let [option, tail @ ..] = rest
+        && option.starts_with('-')
+    {
+        // -o, -s and -t take one to three numbers, unknown options any number
+        let (min, max) = match *option {
+            "-blendu" | "-blendv" | "-bm" | "-boost" | "-cc" | "-clamp" | "-imfchan"
+            | "-texres" | "-type" => (1, 1),
+            "-mm" => (2, 2),
+            "-o" | "-s" | "-t" => (1, 3),
+            _ => (0, usize::MAX),
+        };
+        let numbers = tail
+            .iter()
+            .skip(min)
+            .take(max - min)
+            .take_while(|value| parse_real(value).is_some())
+            .count();
+        rest = tail.get(min + numbers..)?;
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    let filename = rest.join(" ");
+    Some(Texture::load(base_dir.join(filename.replace('\\', "/"))))
+}
+
+// Texture paths are resolved against `base_dir`
+pub fn parse_mtl_str(file_string: &str, base_dir: &Path) -> Vec<Material> {
+    let mut materials: Vec<Material> = Vec::new();
+    for (_, line) in LogicalLines::new(file_string) {
+        let tokens = tokenize(&line);
+        let Some((&first_element, values)) = tokens.split_first() else {
+            continue;
+        };
+        if first_element == "newmtl" {
+            materials.push(Material::new(&values.join(" ")));
+            continue;
+        }
+        // Statements before the first newmtl have nothing to apply to
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        match first_element {
+            "Ka" => material.ambient = parse_color(values).unwrap_or(material.ambient.clone()),
+            "Kd" => material.diffuse = parse_color(values).unwrap_or(material.diffuse.clone()),
+            "Ks" => material.specular = parse_color(values).unwrap_or(material.specular.clone()),
+            "Ns" => material.shininess = parse_scalar(values).unwrap_or(material.shininess),
+            "d" => material.dissolve = parse_scalar(values).unwrap_or(material.dissolve),
+            "Tr" => {
+                material.dissolve = parse_scalar(values).map_or(material.dissolve, |tr| 1. - tr)
+            }
+            "Pm" => material.metallic = parse_scalar(values).unwrap_or(material.metallic),
+            "Pr" => material.roughness = parse_scalar(values).unwrap_or(material.roughness),
+            "illum" => {
+                if let Some(Ok(illumination)) = values.first().map(|value| value.parse::<u32>()) {
+                    material.illumination = illumination;
+                }
+            }
+            "map_Kd" => material.diffuse_map = parse_texture(values, base_dir),
+            "map_Bump" | "map_bump" | "bump" => material.bump_map = parse_texture(values, base_dir),
+            "map_Ks" => material.specular_map = parse_texture(values, base_dir),
+            _ => {}
+        }
+    }
+    materials
+}
+
+pub fn parse_mtl_file(path: &Path) -> Result<Vec<Material>> {
+    let file_string = fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    Ok(parse_mtl_str(&file_string, base_dir))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{math::Vector3, obj::mtl::parse_mtl_str};
+
+    #[test]
+    fn parse_mtl_materials() {
+        let mtl_str = "# comment
+newmtl skin
+Ka\t0.1 0.1 \\
+   0.1 # ambient
+Kd 0.8 0.6 0.5
+Ks 0.5
+Ns 32
+Tr 0.25
+illum 1
+map_Kd -s 1 1 1 textures/skin_diffuse.tga
+map_Bump -bm 0.5 textures/skin_nm.tga
+map_Ks -mm 0 1 -o 0.5 0.5 -clamp on skin spec.png
+
+newmtl metal
+d 0.5
+Pm 1
+Pr 0.2
+";
+        let materials = parse_mtl_str(mtl_str, Path::new("assets"));
+        assert_eq!(2, materials.len());
+
+        let skin = &materials[0];
+        assert_eq!("skin", skin.name);
+        assert_eq!(Vector3::new([0.1, 0.1, 0.1]), skin.ambient);
+        assert_eq!(Vector3::new([0.8, 0.6, 0.5]), skin.diffuse);
+        assert_eq!(Vector3::new([0.5, 0.5, 0.5]), skin.specular);
+        assert_eq!(32., skin.shininess);
+        assert_eq!(0.75, skin.dissolve);
+        assert_eq!(1, skin.illumination);
+        let diffuse_map = skin.diffuse_map.as_ref().unwrap();
+        assert_eq!(
+            Path::new("assets/textures/skin_diffuse.tga"),
+            diffuse_map.path
+        );
+        // Missing file, nothing to load
+        assert!(diffuse_map.image.is_none());
+        assert_eq!(
+            Path::new("assets/textures/skin_nm.tga"),
+            skin.bump_map.as_ref().unwrap().path
+        );
+        assert_eq!(
+            Path::new("assets/skin spec.png"),
+            skin.specular_map.as_ref().unwrap().path
+        );
+
+        let metal = &materials[1];
+        assert_eq!(0.5, metal.dissolve);
+        assert_eq!(Vector3::new([1., 1., 1.]), metal.diffuse);
+        assert_eq!((1., 0.2), (metal.metallic, metal.roughness));
+        assert_eq!((0., 1.), (skin.metallic, skin.roughness));
+    }
+}
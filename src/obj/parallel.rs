@@ -0,0 +1,297 @@
+use crate::{
+    math::Vector3,
+    obj::{
+        ObjFile, ObjParseOptions, ParseMode,
+        lexer::{LogicalLines, split_continuation, tokenize},
+        parser::{
+            Element, ObjParser, RawCorner, material_dir, parse_normal, parse_texture_coord,
+            parse_vertex, resolve_corners,
+        },
+    },
+};
+use anyhow::{Result, anyhow};
+
+use std::{fs, path::Path, thread};
+
+// Below this, splitting a file costs more than it saves
+const MIN_CHUNK_BYTES: usize = 1 << 20;
+
+// Faces, lines and points can't be resolved inside a chunk, positive indexes may point
+// into earlier chunks and negative ones depend on how many verticies came before the chunk
+struct PendingElement {
+    element: Element,
+    line: usize,
+    corners: Vec<RawCorner>,
+    // Verticies, texture coordinates and normals defined earlier in the chunk
+    counts: [usize; 3],
+}
+
+// Everything that isn't geometry (usemtl, o, g, s, ...) is replayed in order when stitching
+struct PendingStatement {
+    // Number of elements in the chunk before the statement
+    element: usize,
+    line: usize,
+    text: String,
+}
+
+// Line numbers here are relative to the start of the chunk
+#[derive(Default)]
+struct ChunkResult {
+    lines: usize,
+    verticies: Vec<(Vector3<f64>, Option<Vector3<f64>>)>,
+    texture_coords: Vec<Vector3<f64>>,
+    normals: Vec<Vector3<f64>>,
+    elements: Vec<PendingElement>,
+    statements: Vec<PendingStatement>,
+    problems: Vec<(usize, String)>,
+}
+
+fn parse_chunk(text: &str, mode: ParseMode) -> ChunkResult {
+    let mut res = ChunkResult {
+        lines: text.bytes().filter(|&b| b == b'\n').count(),
+        ..Default::default()
+    };
+    for (line, statement) in LogicalLines::new(text) {
+        let tokens = tokenize(&statement);
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        let counts = [
+            res.verticies.len(),
+            res.texture_coords.len(),
+            res.normals.len(),
+        ];
+        let parsed = match keyword {
+            "v" => parse_vertex(args).map(|vertex| res.verticies.push(vertex)),
+            "vt" => parse_texture_coord(args)
+                .map(|texture_coord| res.texture_coords.push(texture_coord)),
+            "vn" => parse_normal(args).map(|normal| res.normals.push(normal)),
+            "f" | "l" | "p" => {
+                let element = Element::from_keyword(keyword).expect("matched element keyword");
+                element.parse_corners(args).map(|corners| {
+                    res.elements.push(PendingElement {
+                        element,
+                        line,
+                        corners,
+                        counts,
+                    })
+                })
+            }
+            _ => {
+                res.statements.push(PendingStatement {
+                    element: res.elements.len(),
+                    line,
+                    text: statement.into_owned(),
+                });
+                Ok(())
+            }
+        };
+        if let Err(e) = parsed {
+            res.problems.push((line, e.to_string()));
+            // Nothing after the first error matters in strict mode
+            if mode == ParseMode::Strict {
+                break;
+            }
+        }
+    }
+    res
+}
+
+// Splits at line boundaries, never between a line ending in '\' and its continuation
+fn split_chunks(text: &str, count: usize) -> Vec<&str> {
+    let bytes = text.as_bytes();
+    let target = (text.len() / count.max(1)).max(1);
+    let mut chunks = Vec::with_capacity(count);
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + target).min(text.len());
+        loop {
+            let Some(offset) = bytes[end..].iter().position(|&b| b == b'\n') else {
+                end = text.len();
+                break;
+            };
+            let line_end = end + offset;
+            end = line_end + 1;
+            let line_start = text[..line_end].rfind('\n').map_or(0, |i| i + 1);
+            let (_, continued) =
+                split_continuation(text[line_start..line_end].trim_end_matches('\r'));
+            if !continued {
+                break;
+            }
+        }
+        chunks.push(&text[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+fn stitch(
+    chunks: Vec<ChunkResult>,
+    file: Option<&Path>,
+    options: &ObjParseOptions,
+) -> Result<ObjFile> {
+    let mut parser = ObjParser::new(file, options);
+    let mut line_offset = 0;
+    for chunk in chunks {
+        let offsets = [
+            parser.obj.verticies.len(),
+            parser.obj.texture_coords.len(),
+            parser.obj.normals.len(),
+        ];
+        for (vertex, color) in chunk.verticies {
+            parser.obj.push_vertex(vertex, color);
+        }
+        parser.obj.texture_coords.extend(chunk.texture_coords);
+        parser.obj.normals.extend(chunk.normals);
+
+        let mut statements = chunk.statements.into_iter().peekable();
+        for (index, pending) in chunk.elements.into_iter().enumerate() {
+            while let Some(statement) = statements.next_if(|s| s.element <= index) {
+                parser.parse_line(line_offset + statement.line, &statement.text)?;
+            }
+            let counts = [0, 1, 2].map(|i| offsets[i] + pending.counts[i]);
+            match resolve_corners(&pending.corners, counts) {
+                Ok(verticies) => pending.element.push(&mut parser.obj, verticies),
+                Err(e) => parser.report(line_offset + pending.line, e.to_string())?,
+            }
+        }
+        for statement in statements {
+            parser.parse_line(line_offset + statement.line, &statement.text)?;
+        }
+        for (line, reason) in chunk.problems {
+            parser.report(line_offset + line, reason)?;
+        }
+        line_offset += chunk.lines;
+    }
+    // Chunk problems are reported after their chunk's faces, put them back in file order
+    parser.obj.warnings.sort_by_key(|warning| warning.line);
+    parser.finish(&material_dir(options, file))
+}
+
+fn parse_parallel(text: &str, file: Option<&Path>, options: &ObjParseOptions) -> Result<ObjFile> {
+    let count = options.threads.unwrap_or_else(|| {
+        let available = thread::available_parallelism().map_or(1, |n| n.get());
+        available.min(text.len() / MIN_CHUNK_BYTES).max(1)
+    });
+    let chunks = split_chunks(text, count);
+    let results = thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| scope.spawn(|| parse_chunk(chunk, options.mode)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .map_err(|_| anyhow!("OBJ parsing thread panicked"))
+            })
+            .collect::<Result<Vec<ChunkResult>>>()
+    })?;
+    stitch(results, file, options)
+}
+
+// Reads the whole file up front, then parses chunks of it on separate threads.
+// Gives the same ObjFile as parse_obj_file_with_options.
+pub fn parse_obj_file_parallel(path: &Path, options: &ObjParseOptions) -> Result<ObjFile> {
+    let bytes = fs::read(path)?;
+    parse_parallel(&String::from_utf8_lossy(&bytes), Some(path), options)
+}
+
+pub fn parse_obj_str_parallel(obj_str: &str, options: &ObjParseOptions) -> Result<ObjFile> {
+    parse_parallel(obj_str, None, options)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::obj::{
+        ObjFile, ObjParseError, ObjParseOptions, ParseMode, parse_obj_file,
+        parse_obj_file_parallel, parse_obj_str, parse_obj_str_parallel,
+    };
+
+    fn assert_same(expected: &ObjFile, res: &ObjFile) {
+        assert_eq!(expected.verticies, res.verticies);
+        assert_eq!(expected.texture_coords, res.texture_coords);
+        assert_eq!(expected.normals, res.normals);
+        assert_eq!(expected.faces, res.faces);
+        assert_eq!(expected.polylines, res.polylines);
+        assert_eq!(expected.points, res.points);
+        assert_eq!(expected.triangles.len(), res.triangles.len());
+        assert_eq!(expected.objects, res.objects);
+        assert_eq!(expected.groups, res.groups);
+        assert_eq!(expected.smoothing_groups, res.smoothing_groups);
+        assert_eq!(expected.material_ranges, res.material_ranges);
+        assert_eq!(expected.warnings, res.warnings);
+    }
+
+    fn options(threads: usize, mode: ParseMode) -> ObjParseOptions {
+        ObjParseOptions {
+            mode,
+            threads: Some(threads),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parallel_matches_sequential_diablo() {
+        let path = Path::new("./assets/diablo.obj");
+        let expected = parse_obj_file(path).unwrap();
+        for threads in [1, 3, 8] {
+            let res = parse_obj_file_parallel(path, &options(threads, ParseMode::Lenient)).unwrap();
+            assert_same(&expected, &res);
+        }
+    }
+
+    #[test]
+    fn parallel_relative_indices_and_state() {
+        let mut obj_str = String::from("g first\ns 1\n");
+        for i in 0..40 {
+            obj_str.push_str(&format!("v {} 0 0\nv 0 {} 0\nv 0 0 {}\n", i, i, i));
+            obj_str.push_str("f -3 -2 \\\n -1\n");
+            if i % 7 == 0 {
+                obj_str.push_str(&format!("g group{}\nf 1 2 {}\n", i, 3 * i + 4));
+            }
+            if i % 11 == 0 {
+                obj_str.push_str("s off\nf 1 2 x\nl -1 -2 1\np -3 2\n");
+            }
+        }
+        let expected = parse_obj_str(&obj_str, &ObjParseOptions::default()).unwrap();
+        assert!(!expected.warnings.is_empty());
+        for threads in [2, 5, 16, 64] {
+            let res =
+                parse_obj_str_parallel(&obj_str, &options(threads, ParseMode::Lenient)).unwrap();
+            assert_same(&expected, &res);
+        }
+    }
+
+    #[test]
+    fn parallel_strict_first_error() {
+        let mut obj_str = String::new();
+        for i in 0..30 {
+            obj_str.push_str(&format!("v {} 0 0\n", i));
+        }
+        // Out of range face comes before the syntax error in a later chunk
+        obj_str.push_str("f 1 2 99\n");
+        for i in 0..30 {
+            obj_str.push_str(&format!("v {} 1 0\n", i));
+        }
+        obj_str.push_str("v 1 2\n");
+
+        let expected = parse_obj_str(&obj_str, &options(1, ParseMode::Strict))
+            .err()
+            .unwrap()
+            .downcast::<ObjParseError>()
+            .unwrap();
+        assert_eq!(31, expected.line);
+        for threads in [2, 4, 7] {
+            let res = parse_obj_str_parallel(&obj_str, &options(threads, ParseMode::Strict))
+                .err()
+                .unwrap()
+                .downcast::<ObjParseError>()
+                .unwrap();
+            assert_eq!(expected, res);
+        }
+    }
+}
@@ -0,0 +1,996 @@
+use crate::{
+    math::Vector3,
+    obj::{
+        MaterialRange, ObjFile, SmoothingGroup, SubMesh,
+        lexer::{parse_integer, parse_real, split_continuation, tokenize},
+        mtl::{Material, parse_mtl_file},
+    },
+    types::{Face, FaceVertex, Polyline},
+};
+use anyhow::{Result, anyhow};
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+// A problem with one line of an OBJ file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjParseError {
+    pub file: Option<PathBuf>,
+    // One based, like editors show them
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Display for ObjParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}: {}", file.display(), self.line, self.reason),
+            None => write!(f, "line {}: {}", self.line, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for ObjParseError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ParseMode {
+    // Skip bad lines and collect them in ObjFile::warnings
+    #[default]
+    Lenient,
+    // Fail on the first bad line
+    Strict,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjParseOptions {
+    pub mode: ParseMode,
+    // Where mtllib paths are resolved from.
+    // Defaults to the OBJ file's directory, or the working directory for readers.
+    pub material_dir: Option<PathBuf>,
+    // Chunks the parallel parser splits the file into.
+    // Defaults to the available parallelism, fewer for small files.
+    pub threads: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseProgress {
+    pub bytes_read: u64,
+    // Only known when parsing a file
+    pub total_bytes: Option<u64>,
+    pub lines_read: usize,
+}
+
+pub type ProgressCallback<'a> = &'a mut dyn FnMut(&ParseProgress);
+
+// How many lines go by between progress callbacks
+const PROGRESS_INTERVAL: usize = 1 << 16;
+
+// Reads the numeric components of a v, vt or vn statement.
+// The first `required` names must be present, the rest are optional.
+fn parse_components(args: &[&str], names: &[&str], required: usize) -> Result<Vec<f64>> {
+    if args.len() < required {
+        return Err(anyhow!("Missing {} component", names[args.len()]));
+    }
+    if let Some(extra) = args.get(names.len()) {
+        return Err(anyhow!("Unexpected component \"{}\"", extra));
+    }
+    args.iter()
+        .zip(names)
+        .map(|(arg, name)| {
+            parse_real(arg).ok_or_else(|| anyhow!("Invalid {} component \"{}\"", name, arg))
+        })
+        .collect()
+}
+
+// "v x y z [w]", or "v x y z [w] r g b" with a vertex color as scanners write it.
+// The weight only matters for rational curves, so it's checked but dropped.
+pub(super) fn parse_vertex(args: &[&str]) -> Result<(Vector3<f64>, Option<Vector3<f64>>)> {
+    let names: &[&str] = match args.len() {
+        6 => &["x", "y", "z", "r", "g", "b"],
+        7 => &["x", "y", "z", "w", "r", "g", "b"],
+        _ => &["x", "y", "z", "w"],
+    };
+    let components = parse_components(args, names, 3)?;
+    let position = Vector3::new([components[0], components[1], components[2]]);
+    let color = match components.len() {
+        6 | 7 => {
+            let rgb = &components[components.len() - 3..];
+            Some(Vector3::new([rgb[0], rgb[1], rgb[2]]))
+        }
+        _ => None,
+    };
+    Ok((position, color))
+}
+
+// "vt u [v [w]]"
+pub(super) fn parse_texture_coord(args: &[&str]) -> Result<Vector3<f64>> {
+    let components = parse_components(args, &["u", "v", "w"], 1)?;
+    let component = |i: usize| components.get(i).copied().unwrap_or(0.);
+    Ok(Vector3::new([component(0), component(1), component(2)]))
+}
+
+// "vn x y z"
+pub(super) fn parse_normal(args: &[&str]) -> Result<Vector3<f64>> {
+    let components = parse_components(args, &["x", "y", "z"], 3)?;
+    Ok(Vector3::new([components[0], components[1], components[2]]))
+}
+
+// OBJ indexes are one based. Negative ones count back from the latest element,
+// so -1 is the element defined just before the face.
+fn resolve_index(index: isize, count: usize, kind: &str) -> Result<usize> {
+    let resolved = match index {
+        1.. => Some(index.unsigned_abs() - 1),
+        ..0 => count.checked_sub(index.unsigned_abs()),
+        0 => None,
+    };
+    match resolved {
+        Some(resolved) if resolved < count => Ok(resolved),
+        _ => Err(anyhow!(
+            "{} index {} out of range, {} defined",
+            kind,
+            index,
+            count
+        )),
+    }
+}
+
+// Vertex, texture coordinate and normal indexes as written in the file
+pub(super) type RawCorner = (isize, Option<isize>, Option<isize>);
+
+// Splits "v", "v/vt", "v//vn" and "v/vt/vn" into their raw indexes
+fn parse_face_vertex(chunk: &str) -> Result<RawCorner> {
+    let invalid = || anyhow!("Invalid face vertex \"{}\"", chunk);
+    let mut parts = chunk.split('/');
+    let vertex = parts.next().and_then(parse_integer).ok_or_else(invalid)?;
+    let mut optional = || match parts.next() {
+        None | Some("") => Ok(None),
+        Some(part) => parse_integer(part).map(Some).ok_or_else(invalid),
+    };
+    let texture = optional()?;
+    let normal = optional()?;
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok((vertex, texture, normal))
+}
+
+// Statements that reference verticies by index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Element {
+    // "f", any of "v", "v/vt", "v//vn" and "v/vt/vn" per corner, at least 3 corners
+    Face,
+    // "l", "v" or "v/vt" per vertex, at least 2 verticies
+    Polyline,
+    // "p", plain vertex indexes
+    Points,
+}
+
+impl Element {
+    pub(super) fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "f" => Some(Element::Face),
+            "l" => Some(Element::Polyline),
+            "p" => Some(Element::Points),
+            _ => None,
+        }
+    }
+
+    pub(super) fn parse_corners(self, args: &[&str]) -> Result<Vec<RawCorner>> {
+        let raw_corners = match self {
+            Element::Points => args
+                .iter()
+                .map(|arg| {
+                    parse_integer(arg)
+                        .map(|vertex| (vertex, None, None))
+                        .ok_or_else(|| anyhow!("Invalid point vertex \"{}\"", arg))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Element::Face | Element::Polyline => args
+                .iter()
+                .map(|chunk| parse_face_vertex(chunk))
+                .collect::<Result<Vec<_>>>()?,
+        };
+        let (name, minimum) = match self {
+            Element::Face => ("Face", 3),
+            Element::Polyline => ("Line", 2),
+            Element::Points => ("Point statement", 1),
+        };
+        if raw_corners.len() < minimum {
+            return Err(anyhow!(
+                "{} needs at least {} verticies, found {}",
+                name,
+                minimum,
+                raw_corners.len()
+            ));
+        }
+        if self == Element::Polyline && raw_corners.iter().any(|corner| corner.2.is_some()) {
+            return Err(anyhow!("Line verticies can't have normals"));
+        }
+        Ok(raw_corners)
+    }
+
+    pub(super) fn push(self, obj: &mut ObjFile, verticies: Vec<FaceVertex>) {
+        match self {
+            Element::Face => obj.faces.push(Face { verticies }),
+            Element::Polyline => obj.polylines.push(Polyline { verticies }),
+            Element::Points => obj
+                .points
+                .extend(verticies.iter().map(|corner| corner.vertex)),
+        }
+    }
+}
+
+// `counts` are how many verticies, texture coordinates and normals were defined before the element
+pub(super) fn resolve_corners(
+    raw_corners: &[RawCorner],
+    counts: [usize; 3],
+) -> Result<Vec<FaceVertex>> {
+    let [vertex_count, texture_count, normal_count] = counts;
+    raw_corners
+        .iter()
+        .map(|&(vertex, texture, normal)| {
+            Ok(FaceVertex {
+                vertex: resolve_index(vertex, vertex_count, "Vertex")?,
+                texture: texture
+                    .map(|index| resolve_index(index, texture_count, "Texture coordinate"))
+                    .transpose()?,
+                normal: normal
+                    .map(|index| resolve_index(index, normal_count, "Normal"))
+                    .transpose()?,
+                tangent: None,
+            })
+        })
+        .collect()
+}
+
+// Tracks the faces a state statement (usemtl, o, g, s) applies to
+struct FaceRanges<T> {
+    current: Option<(T, usize)>,
+    ranges: Vec<(T, Range<usize>)>,
+}
+
+impl<T> FaceRanges<T> {
+    fn new() -> Self {
+        FaceRanges {
+            current: None,
+            ranges: Vec::new(),
+        }
+    }
+
+    fn switch(&mut self, value: Option<T>, face_count: usize) {
+        if let Some((previous, start)) = self.current.take()
+            && start < face_count
+        {
+            self.ranges.push((previous, start..face_count));
+        }
+        self.current = value.map(|value| (value, face_count));
+    }
+
+    fn finish(&mut self, face_count: usize) -> Vec<(T, Range<usize>)> {
+        self.switch(None, face_count);
+        std::mem::take(&mut self.ranges)
+    }
+}
+
+pub(super) struct ObjParser<'a> {
+    pub(super) obj: ObjFile,
+    file: Option<PathBuf>,
+    options: &'a ObjParseOptions,
+    // Line of the mtllib statement and the library
+    libraries: Vec<(usize, String)>,
+    // Line of the usemtl statement and the material name
+    materials: FaceRanges<(usize, String)>,
+    objects: FaceRanges<String>,
+    groups: FaceRanges<Vec<String>>,
+    smoothing_groups: FaceRanges<u32>,
+}
+
+impl<'a> ObjParser<'a> {
+    pub(super) fn new(file: Option<&Path>, options: &'a ObjParseOptions) -> Self {
+        ObjParser {
+            obj: ObjFile::default(),
+            file: file.map(Path::to_path_buf),
+            options,
+            libraries: Vec::new(),
+            materials: FaceRanges::new(),
+            objects: FaceRanges::new(),
+            groups: FaceRanges::new(),
+            smoothing_groups: FaceRanges::new(),
+        }
+    }
+
+    // Strict mode turns the problem into an error, lenient mode keeps going
+    pub(super) fn report(&mut self, line: usize, reason: String) -> Result<()> {
+        match self.options.mode {
+            ParseMode::Strict => Err(self.error(line, reason).into()),
+            ParseMode::Lenient => {
+                self.warn(line, reason);
+                Ok(())
+            }
+        }
+    }
+
+    // Recorded in either mode, for problems that never stop the parse
+    fn warn(&mut self, line: usize, reason: String) {
+        let warning = self.error(line, reason);
+        self.obj.warnings.push(warning);
+    }
+
+    fn error(&self, line: usize, reason: String) -> ObjParseError {
+        ObjParseError {
+            file: self.file.clone(),
+            line,
+            reason,
+        }
+    }
+
+    // `line` is a whole statement, continuations already joined
+    pub(super) fn parse_line(&mut self, line_number: usize, line: &str) -> Result<()> {
+        let tokens = tokenize(line);
+        let Some((&keyword, args)) = tokens.split_first() else {
+            return Ok(());
+        };
+        let mut names = args.iter().copied();
+        let face_count = self.obj.faces.len();
+        let parsed = match keyword {
+            "v" => parse_vertex(args).map(|(vertex, color)| self.obj.push_vertex(vertex, color)),
+            "vt" => parse_texture_coord(args)
+                .map(|texture_coord| self.obj.texture_coords.push(texture_coord)),
+            "vn" => parse_normal(args).map(|normal| self.obj.normals.push(normal)),
+            "f" | "l" | "p" => {
+                let element = Element::from_keyword(keyword).expect("matched element keyword");
+                let counts = [
+                    self.obj.verticies.len(),
+                    self.obj.texture_coords.len(),
+                    self.obj.normals.len(),
+                ];
+                element
+                    .parse_corners(args)
+                    .and_then(|raw_corners| resolve_corners(&raw_corners, counts))
+                    .map(|verticies| element.push(&mut self.obj, verticies))
+            }
+            "mtllib" => {
+                let libraries = names.map(|name| (line_number, name.to_string()));
+                self.libraries.extend(libraries);
+                Ok(())
+            }
+            "usemtl" => {
+                let name = names.collect::<Vec<_>>().join(" ");
+                // A bare "usemtl" goes back to no material
+                let material = Some((line_number, name)).filter(|(_, name)| !name.is_empty());
+                self.materials.switch(material, face_count);
+                Ok(())
+            }
+            "o" => {
+                let name = names.collect::<Vec<_>>().join(" ");
+                self.objects.switch(Some(name), face_count);
+                Ok(())
+            }
+            "g" => {
+                let names: Vec<String> = names.map(String::from).collect();
+                // A bare "g" goes back to the default, ungrouped state
+                self.groups
+                    .switch(Some(names).filter(|n| !n.is_empty()), face_count);
+                Ok(())
+            }
+            "s" => match names.next() {
+                // "s off" and "s 0" both turn smoothing off
+                Some("off") => {
+                    self.smoothing_groups.switch(None, face_count);
+                    Ok(())
+                }
+                Some(id) => id
+                    .parse::<u32>()
+                    .map(|id| {
+                        self.smoothing_groups
+                            .switch(Some(id).filter(|&id| id != 0), face_count)
+                    })
+                    .map_err(|_| anyhow!("Invalid smoothing group \"{}\"", id)),
+                None => Err(anyhow!("Missing smoothing group")),
+            },
+            _ => Ok(()),
+        };
+        match parsed {
+            Ok(()) => Ok(()),
+            Err(e) => self.report(line_number, e.to_string()),
+        }
+    }
+
+    // Material libraries are looked up relative to `base_dir`
+    fn load_materials(
+        &mut self,
+        named_ranges: Vec<((usize, String), Range<usize>)>,
+        base_dir: &Path,
+    ) -> Result<()> {
+        for (line, library) in std::mem::take(&mut self.libraries) {
+            match parse_mtl_file(&base_dir.join(&library)) {
+                Ok(materials) => {
+                    for material in &materials {
+                        self.check_textures(line, material);
+                    }
+                    self.obj.materials.extend(materials);
+                }
+                Err(e) => self.report(
+                    line,
+                    format!("Could not read material library {}: {}", library, e),
+                )?,
+            }
+        }
+        for ((line, name), faces) in named_ranges {
+            // Later definitions win, same as re-declaring a material in a library
+            match self.obj.materials.iter().rposition(|m| m.name == name) {
+                Some(material) => self
+                    .obj
+                    .material_ranges
+                    .push(MaterialRange { material, faces }),
+                None => self.report(line, format!("Unknown material \"{}\"", name))?,
+            }
+        }
+        Ok(())
+    }
+
+    // Only TGA textures can be decoded, so a texture that fails to load is
+    // a warning even in strict mode
+    fn check_textures(&mut self, line: usize, material: &Material) {
+        let textures = [
+            &material.diffuse_map,
+            &material.bump_map,
+            &material.specular_map,
+        ];
+        for texture in textures.into_iter().flatten() {
+            if texture.image.is_none() {
+                self.warn(
+                    line,
+                    format!(
+                        "Could not load texture {} for material \"{}\"",
+                        texture.path.display(),
+                        material.name
+                    ),
+                );
+            }
+        }
+    }
+
+    pub(super) fn finish(mut self, base_dir: &Path) -> Result<ObjFile> {
+        let face_count = self.obj.faces.len();
+        self.obj.objects = self
+            .objects
+            .finish(face_count)
+            .into_iter()
+            .map(|(name, faces)| SubMesh { name, faces })
+            .collect();
+        self.obj.groups = self
+            .groups
+            .finish(face_count)
+            .into_iter()
+            .flat_map(|(names, faces)| {
+                names.into_iter().map(move |name| SubMesh {
+                    name,
+                    faces: faces.clone(),
+                })
+            })
+            .collect();
+        self.obj.smoothing_groups = self
+            .smoothing_groups
+            .finish(face_count)
+            .into_iter()
+            .map(|(id, faces)| SmoothingGroup { id, faces })
+            .collect();
+        let named_ranges = self.materials.finish(face_count);
+        self.load_materials(named_ranges, base_dir)?;
+        self.obj.triangulate();
+        Ok(self.obj)
+    }
+}
+
+pub fn parse_obj_file(path: &Path) -> Result<ObjFile> {
+    parse_obj_file_with_options(path, &ObjParseOptions::default(), None)
+}
+
+pub fn parse_obj_file_with_options(
+    path: &Path,
+    options: &ObjParseOptions,
+    progress: Option<ProgressCallback>,
+) -> Result<ObjFile> {
+    let file = File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    parse_stream(
+        BufReader::new(file),
+        Some(path),
+        Some(total_bytes),
+        options,
+        progress,
+    )
+}
+
+// Parses line by line, only the current statement is held in memory
+pub fn parse_obj_reader<R: BufRead>(
+    reader: R,
+    options: &ObjParseOptions,
+    progress: Option<ProgressCallback>,
+) -> Result<ObjFile> {
+    parse_stream(reader, None, None, options, progress)
+}
+
+pub fn parse_obj_str(obj_str: &str, options: &ObjParseOptions) -> Result<ObjFile> {
+    parse_obj_reader(obj_str.as_bytes(), options, None)
+}
+
+pub(super) fn material_dir(options: &ObjParseOptions, file: Option<&Path>) -> PathBuf {
+    match (&options.material_dir, file) {
+        (Some(material_dir), _) => material_dir.clone(),
+        (None, Some(file)) => file.parent().unwrap_or(Path::new("")).to_path_buf(),
+        (None, None) => PathBuf::new(),
+    }
+}
+
+fn parse_stream<R: BufRead>(
+    mut reader: R,
+    file: Option<&Path>,
+    total_bytes: Option<u64>,
+    options: &ObjParseOptions,
+    mut progress: Option<ProgressCallback>,
+) -> Result<ObjFile> {
+    let base_dir = material_dir(options, file);
+    let mut parser = ObjParser::new(file, options);
+    let mut status = ParseProgress {
+        bytes_read: 0,
+        total_bytes,
+        lines_read: 0,
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut statement = String::new();
+    let mut statement_line = 0;
+    let mut continuing = false;
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        status.bytes_read += read as u64;
+        status.lines_read += 1;
+
+        // Exporters occasionally write latin-1 names, those shouldn't stop the parse
+        let line = String::from_utf8_lossy(&buffer);
+        let (content, continued) = split_continuation(line.trim_end_matches(['\n', '\r']));
+        if continuing {
+            statement.push(' ');
+        } else {
+            statement.clear();
+            statement_line = status.lines_read;
+        }
+        statement.push_str(content);
+        continuing = continued;
+        if !continuing {
+            parser.parse_line(statement_line, &statement)?;
+        }
+
+        if status.lines_read.is_multiple_of(PROGRESS_INTERVAL)
+            && let Some(callback) = progress.as_mut()
+        {
+            callback(&status);
+        }
+    }
+    // A continuation on the last line has nothing to join
+    if continuing {
+        parser.parse_line(statement_line, &statement)?;
+    }
+    if let Some(callback) = progress.as_mut() {
+        callback(&status);
+    }
+    parser.finish(&base_dir)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use crate::{
+        math::Vector3,
+        obj::{
+            MaterialRange, ObjFile, ObjParseError, ObjParseOptions, ParseMode, SmoothingGroup,
+            SubMesh, SubMeshFilter,
+            lexer::tokenize,
+            parser::{
+                Element, PROGRESS_INTERVAL, ParseProgress, parse_obj_reader, parse_obj_str,
+                resolve_corners,
+            },
+        },
+        types::{Face, FaceVertex},
+    };
+
+    fn obj_with_counts(verticies: usize, texture_coords: usize, normals: usize) -> ObjFile {
+        ObjFile {
+            verticies: vec![Vector3::new([0., 0., 0.]); verticies],
+            texture_coords: vec![Vector3::new([0., 0., 0.]); texture_coords],
+            normals: vec![Vector3::new([0., 0., 1.]); normals],
+            ..Default::default()
+        }
+    }
+
+    fn parse(obj_str: &str, mode: ParseMode) -> anyhow::Result<ObjFile> {
+        let options = ObjParseOptions {
+            mode,
+            ..Default::default()
+        };
+        parse_obj_str(obj_str, &options)
+    }
+
+    fn parse_face_line(line: &str, obj: &ObjFile) -> anyhow::Result<Face> {
+        let counts = [
+            obj.verticies.len(),
+            obj.texture_coords.len(),
+            obj.normals.len(),
+        ];
+        let raw_corners = Element::Face.parse_corners(&tokenize(line)[1..])?;
+        let verticies = resolve_corners(&raw_corners, counts)?;
+        Ok(Face { verticies })
+    }
+
+    fn vertex_indices(face: &Face) -> Vec<usize> {
+        face.verticies.iter().map(|corner| corner.vertex).collect()
+    }
+
+    #[test]
+    fn parse_quad_face() {
+        let obj = obj_with_counts(4, 4, 4);
+        let face = parse_face_line("f 1/1/1 2/2/2 3/3/3 4/4/4", &obj).unwrap();
+        assert_eq!(vec![0, 1, 2, 3], vertex_indices(&face));
+    }
+
+    #[test]
+    fn parse_face_without_slashes() {
+        let obj = obj_with_counts(7, 0, 0);
+        let face = parse_face_line("f 5 6 7", &obj).unwrap();
+        assert_eq!(vec![4, 5, 6], vertex_indices(&face));
+    }
+
+    #[test]
+    fn parse_face_relative_indices() {
+        let obj = obj_with_counts(5, 3, 0);
+        let face = parse_face_line("f -3/-3 -2/-2 -1/-1", &obj).unwrap();
+        assert_eq!(vec![2, 3, 4], vertex_indices(&face));
+        assert_eq!(Some(0), face.verticies[0].texture);
+    }
+
+    #[test]
+    fn parse_face_out_of_range() {
+        let obj = obj_with_counts(3, 1, 1);
+        assert!(parse_face_line("f 0 1 2", &obj).is_err());
+        assert!(parse_face_line("f 1 2 4", &obj).is_err());
+        assert!(parse_face_line("f -4 -2 -1", &obj).is_err());
+        assert!(parse_face_line("f 1/2 2/1 3/1", &obj).is_err());
+        assert!(parse_face_line("f 1//1 2//1 3//2", &obj).is_err());
+    }
+
+    #[test]
+    fn parse_face_corner_indices() {
+        let obj = obj_with_counts(3, 3, 1);
+        let face = parse_face_line("f 1//1 2//1 3//1", &obj).unwrap();
+        assert_eq!(
+            FaceVertex {
+                vertex: 1,
+                texture: None,
+                normal: Some(0),
+                tangent: None,
+            },
+            face.verticies[1]
+        );
+
+        let face = parse_face_line("f 1/3 2/2 3/1", &obj).unwrap();
+        assert_eq!(
+            FaceVertex {
+                vertex: 0,
+                texture: Some(2),
+                normal: None,
+                tangent: None,
+            },
+            face.verticies[0]
+        );
+    }
+
+    #[test]
+    fn parse_obj_texture_coords_and_normals() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 0.25\nvt 1 1 1\nvt 0\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1\n";
+        let obj = parse(obj_str, ParseMode::Strict).unwrap();
+        assert_eq!(3, obj.texture_coords.len());
+        assert_eq!(Vector3::new([0.5, 0.25, 0.]), obj.texture_coords[0]);
+        assert_eq!(Vector3::new([1., 1., 1.]), obj.texture_coords[1]);
+        assert_eq!(Vector3::new([0., 0., 1.]), obj.normals[0]);
+        assert_eq!(Some(1), obj.triangles[0].two.texture);
+        assert_eq!(Some(0), obj.triangles[0].three.normal);
+    }
+
+    #[test]
+    fn parse_obj_repeated_spaces() {
+        let obj_str =
+            "v  0 0  0\nv 1   0 0\nv 0 1 0 \nvt  0.5  0.25\nvn 0  0   1\nf 1/1/1 2/1/1 3/1/1\n";
+        let obj = parse(obj_str, ParseMode::Strict).unwrap();
+        assert_eq!(3, obj.verticies.len());
+        assert_eq!(Vector3::new([1., 0., 0.]), obj.verticies[1]);
+        assert_eq!(Vector3::new([0.5, 0.25, 0.]), obj.texture_coords[0]);
+        assert_eq!(Vector3::new([0., 0., 1.]), obj.normals[0]);
+    }
+
+    #[test]
+    fn parse_obj_strict_reports_line() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 2 9\nv 1 x 0\n";
+        let err = parse(obj_str, ParseMode::Strict).err().unwrap();
+        let err = err.downcast::<ObjParseError>().unwrap();
+        assert_eq!(5, err.line);
+        assert_eq!(
+            "line 5: Vertex index 9 out of range, 3 defined",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_obj_lenient_collects_warnings() {
+        let obj_str = "v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 x 0
+vt
+f 1 2 3
+f 1 2
+f 1/a 2 3
+s smooth
+usemtl nowhere
+f 3 2 1
+";
+        let obj = parse(obj_str, ParseMode::Lenient).unwrap();
+        assert_eq!(3, obj.verticies.len());
+        assert_eq!(2, obj.faces.len());
+        let lines: Vec<usize> = obj.warnings.iter().map(|w| w.line).collect();
+        assert_eq!(vec![4, 5, 7, 8, 9, 10], lines);
+        assert_eq!("Invalid y component \"x\"", obj.warnings[0].reason);
+        assert_eq!("Unknown material \"nowhere\"", obj.warnings[5].reason);
+    }
+
+    #[test]
+    fn parse_obj_material_ranges() {
+        let dir = std::env::temp_dir().join("tiny_renderer_obj_mtl");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("scene.mtl"),
+            "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n",
+        )
+        .unwrap();
+        let obj_str = "mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+usemtl red
+f 1 2 3
+f 1 2 3
+usemtl missing
+f 1 2 3
+usemtl blue
+f 1 2 3
+";
+        let options = ObjParseOptions {
+            material_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let obj = parse_obj_str(obj_str, &options).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(2, obj.materials.len());
+        assert_eq!(
+            vec![
+                MaterialRange {
+                    material: 0,
+                    faces: 1..3
+                },
+                MaterialRange {
+                    material: 1,
+                    faces: 4..5
+                },
+            ],
+            obj.material_ranges
+        );
+        assert!(obj.face_material(0).is_none());
+        assert_eq!("red", obj.face_material(2).unwrap().name);
+        assert!(obj.face_material(3).is_none());
+        assert_eq!("blue", obj.face_material(4).unwrap().name);
+    }
+
+    #[test]
+    fn parse_obj_strict_texture_warning() {
+        let dir = std::env::temp_dir().join("tiny_renderer_obj_png");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("scene.mtl"), "newmtl wood\nmap_Kd foo.png\n").unwrap();
+        let obj_str = "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl wood\nf 1 2 3\n";
+        let options = ObjParseOptions {
+            mode: ParseMode::Strict,
+            material_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let obj = parse_obj_str(obj_str, &options).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!("wood", obj.face_material(0).unwrap().name);
+        assert_eq!(1, obj.warnings.len());
+        assert_eq!(1, obj.warnings[0].line);
+        assert!(obj.warnings[0].reason.contains("foo.png"));
+    }
+
+    #[test]
+    fn parse_obj_sub_meshes() {
+        let obj_str = "v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+o body
+g torso
+s 1
+f 1 2 3
+f 1 2 3
+g arm left
+s off
+f 1 2 3
+o head
+g
+s 2
+f 1 2 3
+";
+        let obj = parse(obj_str, ParseMode::Strict).unwrap();
+
+        let sub_mesh = |name: &str, faces| SubMesh {
+            name: name.to_string(),
+            faces,
+        };
+        assert_eq!(
+            vec![sub_mesh("body", 1..4), sub_mesh("head", 4..5)],
+            obj.objects
+        );
+        assert_eq!(
+            vec![
+                sub_mesh("torso", 1..3),
+                sub_mesh("arm", 3..4),
+                sub_mesh("left", 3..4)
+            ],
+            obj.groups
+        );
+        assert_eq!(
+            vec![
+                SmoothingGroup { id: 1, faces: 1..3 },
+                SmoothingGroup { id: 2, faces: 4..5 }
+            ],
+            obj.smoothing_groups
+        );
+        assert_eq!(0, obj.face_smoothing_group(3));
+        assert_eq!(2, obj.face_smoothing_group(4));
+
+        let only = obj.face_mask(&SubMeshFilter::Only(vec!["arm".to_string()]));
+        assert_eq!(vec![false, false, false, true, false], only);
+        let skip = obj.face_mask(&SubMeshFilter::Skip(vec!["body".to_string()]));
+        assert_eq!(vec![true, false, false, false, true], skip);
+    }
+
+    #[test]
+    fn parse_obj_messy_whitespace() {
+        // Tabs, padding, trailing comments, CRLF and continued lines
+        let obj_str = "# exported\r
+v\t1.0e-1  0 \t0   # first\r
+v 1 0 0 1.0\r
+v 0 1 0\r
+vt 0.5\r
+vn 0 0 -1E+0\r
+f 1/1/1 \\\r
+  2/1/1 3/1/1\r
+f 1 2 3 # trailing
+";
+        let obj = parse(obj_str, ParseMode::Strict).unwrap();
+        assert_eq!(Vector3::new([0.1, 0., 0.]), obj.verticies[0]);
+        assert_eq!(3, obj.verticies.len());
+        assert_eq!(Vector3::new([0., 0., -1.]), obj.normals[0]);
+        assert_eq!(2, obj.faces.len());
+        assert_eq!(vec![0, 1, 2], vertex_indices(&obj.faces[0]));
+        assert_eq!(Some(0), obj.faces[0].verticies[2].normal);
+    }
+
+    #[test]
+    fn parse_obj_component_counts() {
+        let obj_str = "v 1 2\nv 1 2 3 4 5 6 7 8\nvn 0 0 1 1\nv 1 2 nan\n";
+        let obj = parse(obj_str, ParseMode::Lenient).unwrap();
+        let reasons: Vec<&str> = obj.warnings.iter().map(|w| w.reason.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Missing z component",
+                "Unexpected component \"5\"",
+                "Unexpected component \"1\"",
+                "Invalid z component \"nan\""
+            ],
+            reasons
+        );
+    }
+
+    #[test]
+    fn parse_obj_reader_progress() {
+        let mut obj_str = String::new();
+        for i in 0..PROGRESS_INTERVAL + 10 {
+            obj_str.push_str(&format!("v {} 0 0\n", i));
+        }
+        obj_str.push_str("f 1 2 \\");
+
+        let mut updates: Vec<ParseProgress> = Vec::new();
+        let mut record = |progress: &ParseProgress| updates.push(*progress);
+        let obj = parse_obj_reader(
+            BufReader::with_capacity(64, obj_str.as_bytes()),
+            &ObjParseOptions::default(),
+            Some(&mut record),
+        )
+        .unwrap();
+
+        assert_eq!(PROGRESS_INTERVAL + 10, obj.verticies.len());
+        // Dangling continuation, the face is still short a vertex
+        assert_eq!(1, obj.warnings.len());
+        assert_eq!(PROGRESS_INTERVAL + 11, obj.warnings[0].line);
+        assert_eq!(2, updates.len());
+        assert_eq!(PROGRESS_INTERVAL, updates[0].lines_read);
+        let last = updates.last().unwrap();
+        assert_eq!(obj_str.len() as u64, last.bytes_read);
+        assert_eq!(None, last.total_bytes);
+    }
+
+    #[test]
+    fn parse_obj_vertex_colors() {
+        let obj_str = "v 0 0 0\nv 1 0 0 1 0 0\nv 0 1 0 1.0 0 0.5 0.25\nv 0 0 1 0.5\n";
+        let obj = parse(obj_str, ParseMode::Strict).unwrap();
+        assert_eq!(4, obj.verticies.len());
+        assert_eq!(
+            vec![
+                Vector3::new([1., 1., 1.]),
+                Vector3::new([1., 0., 0.]),
+                Vector3::new([0., 0.5, 0.25]),
+                Vector3::new([1., 1., 1.]),
+            ],
+            obj.vertex_colors
+        );
+        assert_eq!(Vector3::new([0., 1., 0.]), obj.verticies[2]);
+
+        let obj = parse("v 0 0 0\nv 1 0 0\n", ParseMode::Strict).unwrap();
+        assert!(obj.vertex_colors.is_empty());
+
+        // The first vertex keeps its color
+        let obj = parse("v 0 0 0 0 0 1\nv 1 0 0\n", ParseMode::Strict).unwrap();
+        assert_eq!(
+            vec![Vector3::new([0., 0., 1.]), Vector3::new([1., 1., 1.])],
+            obj.vertex_colors
+        );
+    }
+
+    #[test]
+    fn parse_obj_lines_and_points() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nl 1 2 3 1\nl 1/1 -1/1\np 1 -1\nl 1\nl 1//1 2//1\np 4\n";
+        let obj = parse(obj_str, ParseMode::Lenient).unwrap();
+        assert_eq!(2, obj.polylines.len());
+        let line_verticies: Vec<usize> = obj.polylines[0]
+            .verticies
+            .iter()
+            .map(|corner| corner.vertex)
+            .collect();
+        assert_eq!(vec![0, 1, 2, 0], line_verticies);
+        assert_eq!(Some(0), obj.polylines[1].verticies[1].texture);
+        assert_eq!(2, obj.polylines[1].verticies[1].vertex);
+        assert_eq!(vec![0, 2], obj.points);
+
+        let reasons: Vec<&str> = obj.warnings.iter().map(|w| w.reason.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Line needs at least 2 verticies, found 1",
+                "Line verticies can't have normals",
+                "Vertex index 4 out of range, 3 defined"
+            ],
+            reasons
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use crate::{
+    math::Vector3,
+    obj::{ObjFile, mtl::Material},
+    types::FaceVertex,
+};
+use anyhow::Result;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Debug, Clone)]
+pub struct ObjWriteOptions {
+    // Digits after the decimal point. Trailing zeros are dropped.
+    pub precision: usize,
+    // Written as the mtllib statement. write_obj_file fills it in when there are materials.
+    pub material_library: Option<String>,
+}
+
+impl Default for ObjWriteOptions {
+    fn default() -> Self {
+        ObjWriteOptions {
+            precision: 6,
+            material_library: None,
+        }
+    }
+}
+
+fn format_float(value: f64, precision: usize) -> String {
+    let mut str = format!("{:.*}", precision, value);
+    if str.contains('.') {
+        let trimmed = str.trim_end_matches('0').trim_end_matches('.').len();
+        str.truncate(trimmed);
+    }
+    if str == "-0" {
+        str.remove(0);
+    }
+    str
+}
+
+fn format_vector(vector: &Vector3<f64>, components: usize, precision: usize) -> String {
+    vector.get_data()[..components]
+        .iter()
+        .map(|&component| format_float(component, precision))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn format_face_vertex(corner: &FaceVertex) -> String {
+    match (corner.texture, corner.normal) {
+        (None, None) => format!("{}", corner.vertex + 1),
+        (Some(texture), None) => format!("{}/{}", corner.vertex + 1, texture + 1),
+        (None, Some(normal)) => format!("{}//{}", corner.vertex + 1, normal + 1),
+        (Some(texture), Some(normal)) => {
+            format!("{}/{}/{}", corner.vertex + 1, texture + 1, normal + 1)
+        }
+    }
+}
+
+// Per face state, so statements are only written where something changes
+struct FaceState<'a> {
+    objects: Vec<Option<&'a str>>,
+    groups: Vec<Vec<&'a str>>,
+    materials: Vec<Option<&'a str>>,
+    smoothing_groups: Vec<u32>,
+}
+
+impl<'a> FaceState<'a> {
+    fn new(obj: &'a ObjFile) -> Self {
+        let face_count = obj.faces.len();
+        let mut state = FaceState {
+            objects: vec![None; face_count],
+            groups: vec![Vec::new(); face_count],
+            materials: vec![None; face_count],
+            smoothing_groups: vec![0; face_count],
+        };
+        for object in &obj.objects {
+            for face in object.faces.clone().filter(|&face| face < face_count) {
+                state.objects[face] = Some(&object.name);
+            }
+        }
+        for group in &obj.groups {
+            for face in group.faces.clone().filter(|&face| face < face_count) {
+                state.groups[face].push(&group.name);
+            }
+        }
+        for range in &obj.material_ranges {
+            let Some(material) = obj.materials.get(range.material) else {
+                continue;
+            };
+            for face in range.faces.clone().filter(|&face| face < face_count) {
+                state.materials[face] = Some(&material.name);
+            }
+        }
+        for group in &obj.smoothing_groups {
+            for face in group.faces.clone().filter(|&face| face < face_count) {
+                state.smoothing_groups[face] = group.id;
+            }
+        }
+        state
+    }
+}
+
+fn changed<T: PartialEq>(values: &[T], index: usize) -> bool {
+    index == 0 || values[index] != values[index - 1]
+}
+
+pub fn write_obj<W: Write>(obj: &ObjFile, out: &mut W, options: &ObjWriteOptions) -> Result<()> {
+    let precision = options.precision;
+    if let Some(library) = &options.material_library {
+        writeln!(out, "mtllib {}", library)?;
+    }
+    for (index, vertex) in obj.verticies.iter().enumerate() {
+        match obj.vertex_colors.get(index) {
+            Some(color) => writeln!(
+                out,
+                "v {} {}",
+                format_vector(vertex, 3, precision),
+                format_vector(color, 3, precision)
+            )?,
+            None => writeln!(out, "v {}", format_vector(vertex, 3, precision))?,
+        }
+    }
+    for texture_coord in &obj.texture_coords {
+        // Only write w when it's there
+        let components = if texture_coord.z() == 0. { 2 } else { 3 };
+        writeln!(
+            out,
+            "vt {}",
+            format_vector(texture_coord, components, precision)
+        )?;
+    }
+    for normal in &obj.normals {
+        writeln!(out, "vn {}", format_vector(normal, 3, precision))?;
+    }
+
+    let state = FaceState::new(obj);
+    for (index, face) in obj.faces.iter().enumerate() {
+        if changed(&state.objects, index)
+            && let Some(name) = state.objects[index]
+        {
+            writeln!(out, "o {}", name)?;
+        }
+        if changed(&state.groups, index) && (index > 0 || !state.groups[index].is_empty()) {
+            match state.groups[index].as_slice() {
+                [] => writeln!(out, "g")?,
+                names => writeln!(out, "g {}", names.join(" "))?,
+            }
+        }
+        if changed(&state.materials, index) && (index > 0 || state.materials[index].is_some()) {
+            // A bare "usemtl" goes back to no material
+            match state.materials[index] {
+                Some(name) => writeln!(out, "usemtl {}", name)?,
+                None => writeln!(out, "usemtl")?,
+            }
+        }
+        if changed(&state.smoothing_groups, index)
+            && (index > 0 || state.smoothing_groups[index] != 0)
+        {
+            match state.smoothing_groups[index] {
+                0 => writeln!(out, "s off")?,
+                id => writeln!(out, "s {}", id)?,
+            }
+        }
+        let corners: Vec<String> = face.verticies.iter().map(format_face_vertex).collect();
+        writeln!(out, "f {}", corners.join(" "))?;
+    }
+    for polyline in &obj.polylines {
+        let corners: Vec<String> = polyline.verticies.iter().map(format_face_vertex).collect();
+        writeln!(out, "l {}", corners.join(" "))?;
+    }
+    if !obj.points.is_empty() {
+        let points: Vec<String> = obj
+            .points
+            .iter()
+            .map(|point| (point + 1).to_string())
+            .collect();
+        writeln!(out, "p {}", points.join(" "))?;
+    }
+    Ok(())
+}
+
+// Texture paths are written relative to `base_dir` when they sit under it
+pub fn write_mtl<W: Write>(
+    materials: &[Material],
+    out: &mut W,
+    base_dir: &Path,
+    options: &ObjWriteOptions,
+) -> Result<()> {
+    let precision = options.precision;
+    for (index, material) in materials.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "newmtl {}", material.name)?;
+        writeln!(out, "Ka {}", format_vector(&material.ambient, 3, precision))?;
+        writeln!(out, "Kd {}", format_vector(&material.diffuse, 3, precision))?;
+        writeln!(
+            out,
+            "Ks {}",
+            format_vector(&material.specular, 3, precision)
+        )?;
+        writeln!(out, "Ns {}", format_float(material.shininess, precision))?;
+        writeln!(out, "d {}", format_float(material.dissolve, precision))?;
+        writeln!(out, "illum {}", material.illumination)?;
+        // Only PBR materials get these, most readers don't know them
+        if material.metallic != 0. || material.roughness != 1. {
+            writeln!(out, "Pm {}", format_float(material.metallic, precision))?;
+            writeln!(out, "Pr {}", format_float(material.roughness, precision))?;
+        }
+        let maps = [
+            ("map_Kd", &material.diffuse_map),
+            ("map_Bump", &material.bump_map),
+            ("map_Ks", &material.specular_map),
+        ];
+        for (statement, texture) in maps {
+            if let Some(texture) = texture {
+                let path = texture.path.strip_prefix(base_dir).unwrap_or(&texture.path);
+                writeln!(out, "{} {}", statement, path.display())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Materials go to a .mtl file next to the OBJ, named after it
+pub fn write_obj_file(obj: &ObjFile, path: &Path, options: &ObjWriteOptions) -> Result<()> {
+    let mut options = options.clone();
+    if !obj.materials.is_empty() {
+        let mtl_path = path.with_extension("mtl");
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let mut mtl_out = BufWriter::new(File::create(&mtl_path)?);
+        write_mtl(&obj.materials, &mut mtl_out, base_dir, &options)?;
+        mtl_out.flush()?;
+        if options.material_library.is_none() {
+            options.material_library = mtl_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+        }
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    write_obj(obj, &mut out, &options)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        math::Vector3,
+        obj::{
+            MaterialRange, ObjFile, ObjParseOptions,
+            mtl::Material,
+            parse_obj_file, parse_obj_str,
+            writer::{ObjWriteOptions, format_float, write_obj, write_obj_file},
+        },
+    };
+
+    fn assert_close(expected: &[Vector3<f64>], res: &[Vector3<f64>], tolerance: f64) {
+        assert_eq!(expected.len(), res.len());
+        for (a, b) in expected.iter().zip(res) {
+            let difference = a.clone() - b.clone();
+            assert!(difference.norm() <= tolerance);
+        }
+    }
+
+    #[test]
+    fn writer_float_precision() {
+        assert_eq!("1", format_float(1.0, 6));
+        assert_eq!("0.125", format_float(0.125, 6));
+        assert_eq!("0.13", format_float(0.125001, 2));
+        assert_eq!("0", format_float(-0.0000001, 3));
+        assert_eq!("-2.5", format_float(-2.5, 4));
+    }
+
+    #[test]
+    fn writer_round_trip_diablo() {
+        let expected = parse_obj_file(Path::new("./assets/diablo.obj")).unwrap();
+        let path = std::env::temp_dir().join("tiny_renderer_round_trip.obj");
+        write_obj_file(&expected, &path, &ObjWriteOptions::default()).unwrap();
+        let res = parse_obj_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(res.warnings.is_empty());
+        assert_close(&expected.verticies, &res.verticies, 1e-6);
+        assert_close(&expected.texture_coords, &res.texture_coords, 1e-6);
+        assert_close(&expected.normals, &res.normals, 1e-6);
+        assert_eq!(expected.faces, res.faces);
+        assert_eq!(expected.groups, res.groups);
+        assert_eq!(expected.smoothing_groups, res.smoothing_groups);
+    }
+
+    #[test]
+    fn writer_round_trip_groups_and_materials() {
+        let obj_str = "v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 1 0
+vt 0 0
+vt 1 0 0.5
+vn 0 0 1
+o thing
+f 1 2 3
+g arm left
+s 2
+f 1/1 2/2 4/1
+g
+s off
+f 1//1 4//1 3//1
+l 1/1 2/2 4
+p 3 4
+";
+        let mut expected = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        let mut material = Material::new("shiny");
+        material.diffuse = Vector3::new([0.25, 0.5, 1.]);
+        material.shininess = 64.;
+        material.roughness = 0.5;
+        expected.materials.push(material);
+        expected.material_ranges.push(MaterialRange {
+            material: 0,
+            faces: 1..3,
+        });
+
+        let dir = std::env::temp_dir().join("tiny_renderer_writer");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.obj");
+        let options = ObjWriteOptions {
+            precision: 3,
+            ..Default::default()
+        };
+        write_obj_file(&expected, &path, &options).unwrap();
+        let res = parse_obj_file(&path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(written.starts_with("mtllib scene.mtl\n"));
+        assert!(written.contains("vt 1 0 0.5\n"));
+        assert!(written.contains("f 1/1 2/2 4/1\n"));
+        assert!(written.contains("l 1/1 2/2 4\np 3 4\n"));
+        assert!(res.warnings.is_empty());
+        assert_eq!(expected.texture_coords, res.texture_coords);
+        assert_eq!(expected.faces, res.faces);
+        assert_eq!(expected.polylines, res.polylines);
+        assert_eq!(expected.points, res.points);
+        assert_eq!(expected.objects, res.objects);
+        assert_eq!(expected.groups, res.groups);
+        assert_eq!(expected.smoothing_groups, res.smoothing_groups);
+        assert_eq!(expected.material_ranges, res.material_ranges);
+        assert_eq!(Vector3::new([0.25, 0.5, 1.]), res.materials[0].diffuse);
+        assert_eq!(64., res.materials[0].shininess);
+        assert_eq!(0., res.materials[0].metallic);
+        assert_eq!(0.5, res.materials[0].roughness);
+    }
+
+    #[test]
+    fn writer_round_trip_material_reset() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 2 3\nf 1 2 3\n";
+        let mut expected = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        expected.materials.push(Material::new("red"));
+        expected.material_ranges = vec![
+            MaterialRange {
+                material: 0,
+                faces: 0..1,
+            },
+            MaterialRange {
+                material: 0,
+                faces: 2..3,
+            },
+        ];
+
+        let dir = std::env::temp_dir().join("tiny_renderer_writer_reset");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.obj");
+        write_obj_file(&expected, &path, &ObjWriteOptions::default()).unwrap();
+        let res = parse_obj_file(&path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(written.contains("usemtl red\nf 1 2 3\nusemtl\nf 1 2 3\nusemtl red\n"));
+        assert!(res.warnings.is_empty());
+        assert_eq!(expected.material_ranges, res.material_ranges);
+        assert!(res.face_material(1).is_none());
+    }
+
+    #[test]
+    fn writer_without_material_library() {
+        let mut obj = ObjFile {
+            verticies: vec![Vector3::new([0.5, -1., 2.])],
+            ..Default::default()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        write_obj(&obj, &mut out, &ObjWriteOptions::default()).unwrap();
+        assert_eq!("v 0.5 -1 2\n", String::from_utf8(out).unwrap());
+
+        obj.push_vertex(
+            Vector3::new([1., 1., 1.]),
+            Some(Vector3::new([1., 0.5, 0.])),
+        );
+        let mut out: Vec<u8> = Vec::new();
+        write_obj(&obj, &mut out, &ObjWriteOptions::default()).unwrap();
+        assert_eq!(
+            "v 0.5 -1 2 1 1 1\nv 1 1 1 1 0.5 0\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
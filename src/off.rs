@@ -0,0 +1,267 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    math::Vector3,
+    obj::{
+        ObjFile,
+        lexer::{parse_integer, parse_real, strip_comment, tokenize},
+    },
+    types::{Face, FaceVertex},
+};
+
+// Channels are 0..1 when any of them is written as a real, 0..255 otherwise.
+// Deciding per channel would read the 1 in "1 0.5 0" as 1/255.
+fn parse_color(channels: &[&str]) -> Option<Vector3<f64>> {
+    let real = channels.iter().any(|token| token.contains(['.', 'e', 'E']));
+    let channel = |token: &str| {
+        if real {
+            parse_real(token)
+        } else {
+            parse_integer(token).map(|value| value as f64 / 255.)
+        }
+    };
+    let [r, g, b] = [0, 1, 2].map(|i| channel(channels[i]));
+    Some(Vector3::new([r?, g?, b?]))
+}
+
+fn parse_vertex(tokens: &[&str], colored: bool) -> Result<(Vector3<f64>, Option<Vector3<f64>>)> {
+    let expected = if colored { "x y z r g b [a]" } else { "x y z" };
+    let invalid = || anyhow!("Expected \"{}\", found \"{}\"", expected, tokens.join(" "));
+    if tokens.len() < 3 || (colored && tokens.len() < 6) {
+        return Err(invalid());
+    }
+    let position = [0, 1, 2]
+        .map(|i| parse_real(tokens[i]))
+        .into_iter()
+        .collect::<Option<Vec<f64>>>()
+        .ok_or_else(invalid)?;
+    let color = if colored {
+        Some(parse_color(&tokens[3..]).ok_or_else(invalid)?)
+    } else {
+        None
+    };
+    Ok((Vector3::new([position[0], position[1], position[2]]), color))
+}
+
+// "n i0 i1 ... [color]". Face colors have no place in ObjFile and are dropped.
+fn parse_face(tokens: &[&str], vertex_count: usize) -> Result<Face> {
+    let count = tokens
+        .first()
+        .and_then(|token| token.parse::<usize>().ok())
+        .ok_or_else(|| anyhow!("Face must start with its vertex count"))?;
+    if count < 3 {
+        return Err(anyhow!("Face needs at least 3 verticies, found {}", count));
+    }
+    let indices = tokens
+        .get(1..count + 1)
+        .ok_or_else(|| anyhow!("Face has fewer than {} verticies", count))?;
+    let verticies = indices
+        .iter()
+        .map(|token| {
+            let index = token
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid vertex index \"{}\"", token))?;
+            if index >= vertex_count {
+                return Err(anyhow!(
+                    "Vertex index {} out of range, {} defined",
+                    index,
+                    vertex_count
+                ));
+            }
+            Ok(FaceVertex::new(index))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Face { verticies })
+}
+
+// Reads OFF, and COFF with per vertex colors
+pub fn parse_off_str(off_str: &str) -> Result<ObjFile> {
+    let mut lines = off_str
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, tokenize(strip_comment(line))))
+        .filter(|(_, tokens)| !tokens.is_empty());
+
+    let (_, header) = lines.next().ok_or_else(|| anyhow!("OFF file is empty"))?;
+    let keyword = header[0];
+    let (colored, fused) = match (keyword.strip_prefix("COFF"), keyword.strip_prefix("OFF")) {
+        (Some(rest), _) => (true, rest),
+        (None, Some(rest)) => (false, rest),
+        _ => return Err(anyhow!("Unsupported OFF keyword \"{}\"", keyword)),
+    };
+    if !fused.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(anyhow!("Unsupported OFF keyword \"{}\"", keyword));
+    }
+    // The counts can share the keyword's line, some files (ModelNet) even
+    // glue the vertex count to the keyword as in "OFF490 518 0"
+    let counts = match (fused, &header[1..]) {
+        ("", []) => lines.next().map(|(_, tokens)| tokens).unwrap_or_default(),
+        ("", counts) => counts.to_vec(),
+        (fused, counts) => [&[fused], counts].concat(),
+    };
+    let [vertex_count, face_count] =
+        [0, 1].map(|i| counts.get(i).and_then(|c| c.parse::<usize>().ok()));
+    let (Some(vertex_count), Some(face_count)) = (vertex_count, face_count) else {
+        return Err(anyhow!("OFF header needs vertex and face counts"));
+    };
+
+    let mut obj = ObjFile::default();
+    for _ in 0..vertex_count {
+        let (line, tokens) = lines
+            .next()
+            .ok_or_else(|| anyhow!("OFF file ended after {} verticies", obj.verticies.len()))?;
+        let (vertex, color) =
+            parse_vertex(&tokens, colored).map_err(|e| anyhow!("line {}: {}", line, e))?;
+        obj.push_vertex(vertex, color);
+    }
+    for _ in 0..face_count {
+        let (line, tokens) = lines
+            .next()
+            .ok_or_else(|| anyhow!("OFF file ended after {} faces", obj.faces.len()))?;
+        let face =
+            parse_face(&tokens, vertex_count).map_err(|e| anyhow!("line {}: {}", line, e))?;
+        obj.faces.push(face);
+    }
+    obj.triangulate();
+    Ok(obj)
+}
+
+pub fn parse_off_file(path: &Path) -> Result<ObjFile> {
+    let off_str = std::fs::read_to_string(path)?;
+    parse_off_str(&off_str)
+        .map_err(|e| anyhow!("Failed to read OFF file {}: {}", path.display(), e))
+}
+
+// COFF when the mesh has vertex colors. Texture coordinates and normals are dropped.
+pub fn write_off<W: Write>(obj: &ObjFile, out: &mut W) -> Result<()> {
+    let colored = !obj.vertex_colors.is_empty();
+    writeln!(out, "{}", if colored { "COFF" } else { "OFF" })?;
+    // The edge count is optional for readers, 0 is the usual placeholder
+    writeln!(out, "{} {} 0", obj.verticies.len(), obj.faces.len())?;
+    for (index, vertex) in obj.verticies.iter().enumerate() {
+        let [x, y, z] = vertex.get_data();
+        match obj.vertex_colors.get(index) {
+            Some(color) => {
+                let [r, g, b] = color
+                    .get_data()
+                    .map(|channel| (channel.clamp(0., 1.) * 255.).round() as u8);
+                writeln!(out, "{} {} {} {} {} {} 255", x, y, z, r, g, b)?
+            }
+            None => writeln!(out, "{} {} {}", x, y, z)?,
+        }
+    }
+    for face in &obj.faces {
+        let indices: Vec<String> = face
+            .verticies
+            .iter()
+            .map(|corner| corner.vertex.to_string())
+            .collect();
+        writeln!(out, "{} {}", indices.len(), indices.join(" "))?;
+    }
+    Ok(())
+}
+
+pub fn write_off_file(obj: &ObjFile, path: &Path) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_off(obj, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        off::{parse_off_str, write_off},
+    };
+
+    #[test]
+    fn off_pyramid() {
+        let off_str = "OFF
+# square pyramid
+5 5 8
+0 0 0
+1 0 0
+1 1 0
+0 1 0
+0.5 0.5 1
+4 3 2 1 0
+3 0 1 4
+3 1 2 4 255 0 0
+3 2 3 4
+3 3 0 4
+";
+        let obj = parse_off_str(off_str).unwrap();
+        assert_eq!(5, obj.verticies.len());
+        assert_eq!(Vector3::new([0.5, 0.5, 1.]), obj.verticies[4]);
+        assert!(obj.vertex_colors.is_empty());
+        assert_eq!(5, obj.faces.len());
+        assert_eq!(4, obj.faces[0].verticies.len());
+        assert_eq!(6, obj.triangles.len());
+    }
+
+    #[test]
+    fn off_colors_round_trip() {
+        let off_str =
+            "COFF 3 1 0\n0 0 0 255 0 0 255\n1 0 0 0 0.5 0 1\n0 1 0 0 0 255 255\n3 0 1 2\n";
+        let expected = parse_off_str(off_str).unwrap();
+        assert_eq!(
+            vec![
+                Vector3::new([1., 0., 0.]),
+                Vector3::new([0., 0.5, 0.]),
+                Vector3::new([0., 0., 1.])
+            ],
+            expected.vertex_colors
+        );
+
+        let mut out: Vec<u8> = Vec::new();
+        write_off(&expected, &mut out).unwrap();
+        let written = String::from_utf8(out).unwrap();
+        assert!(written.starts_with("COFF\n3 1 0\n0 0 0 255 0 0 255\n"));
+        let res = parse_off_str(&written).unwrap();
+        assert_eq!(expected.verticies, res.verticies);
+        assert_eq!(expected.faces, res.faces);
+        assert_eq!(expected.vertex_colors[0], res.vertex_colors[0]);
+    }
+
+    #[test]
+    fn off_invalid() {
+        assert!(parse_off_str("PLY\n3 1 0\n").is_err());
+        assert!(parse_off_str("OFF\n3 1 0\n0 0 0\n1 0 0\n").is_err());
+        assert!(parse_off_str("OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 3\n").is_err());
+        assert!(parse_off_str("OFF\n3 1 0\n0 0 0\n1 0 0\n0 1 0\n2 0 1\n").is_err());
+        assert!(parse_off_str("COFF\n1 0 0\n0 0 0\n").is_err());
+        assert!(parse_off_str("OFFX 3 1 0\n").is_err());
+    }
+
+    #[test]
+    fn off_real_colors() {
+        let off_str = "COFF\n3 1 0\n0 0 0 1 0.5 0\n1 0 0 1 1 1\n0 1 0 1e0 0 0\n3 0 1 2\n";
+        let obj = parse_off_str(off_str).unwrap();
+        assert_eq!(
+            vec![
+                Vector3::new([1., 0.5, 0.]),
+                Vector3::new([1. / 255., 1. / 255., 1. / 255.]),
+                Vector3::new([1., 0., 0.])
+            ],
+            obj.vertex_colors
+        );
+    }
+
+    #[test]
+    fn off_fused_header() {
+        let off_str = "OFF3 1 0\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        let obj = parse_off_str(off_str).unwrap();
+        assert_eq!(3, obj.verticies.len());
+        assert_eq!(1, obj.faces.len());
+        let coff_str = "COFF3 1\n0 0 0 0 0 255\n1 0 0 0 0 255\n0 1 0 0 0 255\n3 0 1 2\n";
+        let obj = parse_off_str(coff_str).unwrap();
+        assert_eq!(Vector3::new([0., 0., 1.]), obj.vertex_colors[2]);
+    }
+}
@@ -0,0 +1,700 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::SplitAsciiWhitespace,
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    math::Vector3,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PlyFormat {
+    #[default]
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+impl PlyFormat {
+    fn from_header(name: &str) -> Result<Self> {
+        match name {
+            "ascii" => Ok(PlyFormat::Ascii),
+            "binary_little_endian" => Ok(PlyFormat::BinaryLittleEndian),
+            "binary_big_endian" => Ok(PlyFormat::BinaryBigEndian),
+            _ => Err(anyhow!("Unknown PLY format \"{}\"", name)),
+        }
+    }
+
+    fn header_name(&self) -> &'static str {
+        match self {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn from_header(name: &str) -> Result<Self> {
+        match name {
+            "char" | "int8" => Ok(ScalarType::Int8),
+            "uchar" | "uint8" => Ok(ScalarType::UInt8),
+            "short" | "int16" => Ok(ScalarType::Int16),
+            "ushort" | "uint16" => Ok(ScalarType::UInt16),
+            "int" | "int32" => Ok(ScalarType::Int32),
+            "uint" | "uint32" => Ok(ScalarType::UInt32),
+            "float" | "float32" => Ok(ScalarType::Float32),
+            "double" | "float64" => Ok(ScalarType::Float64),
+            _ => Err(anyhow!("Unknown PLY property type \"{}\"", name)),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, ScalarType::Float32 | ScalarType::Float64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PropertyType {
+    Scalar(ScalarType),
+    // Count type, then item type
+    List(ScalarType, ScalarType),
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    format: PlyFormat,
+    elements: Vec<Element>,
+    // Where the body starts
+    length: usize,
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| anyhow!("PLY header has no end_header"))?;
+        let line = std::str::from_utf8(&bytes[offset..offset + end])?;
+        offset += end + 1;
+        line_number += 1;
+        let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+        if line_number == 1 {
+            if tokens != ["ply"] {
+                return Err(anyhow!("Not a PLY file"));
+            }
+            continue;
+        }
+        let error = |reason: String| anyhow!("PLY header line {}: {}", line_number, reason);
+        match tokens.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", name, _version] => format = Some(PlyFormat::from_header(name)?),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| error(format!("Invalid element count \"{}\"", count)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| error("Property before any element".to_string()))?
+                .properties
+                .push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::List(
+                        ScalarType::from_header(count_type)?,
+                        ScalarType::from_header(item_type)?,
+                    ),
+                }),
+            ["property", scalar_type, name] => elements
+                .last_mut()
+                .ok_or_else(|| error("Property before any element".to_string()))?
+                .properties
+                .push(Property {
+                    name: name.to_string(),
+                    kind: PropertyType::Scalar(ScalarType::from_header(scalar_type)?),
+                }),
+            ["end_header"] => break,
+            _ => return Err(error(format!("Unexpected \"{}\"", line.trim()))),
+        }
+    }
+    Ok(Header {
+        format: format.ok_or_else(|| anyhow!("PLY header has no format"))?,
+        elements,
+        length: offset,
+    })
+}
+
+// Every value is read as f64, which holds all PLY types up to uint32 exactly
+enum BodyReader<'a> {
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> BodyReader<'a> {
+    fn new(format: PlyFormat, body: &'a [u8]) -> Result<Self> {
+        Ok(match format {
+            PlyFormat::Ascii => {
+                BodyReader::Ascii(std::str::from_utf8(body)?.split_ascii_whitespace())
+            }
+            PlyFormat::BinaryLittleEndian | PlyFormat::BinaryBigEndian => BodyReader::Binary {
+                data: body,
+                position: 0,
+                big_endian: format == PlyFormat::BinaryBigEndian,
+            },
+        })
+    }
+
+    fn read(&mut self, scalar_type: ScalarType) -> Result<f64> {
+        match self {
+            BodyReader::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| anyhow!("PLY body ended early"))?;
+                let value: f64 = token
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid PLY value \"{}\"", token))?;
+                if !scalar_type.is_float() && value.fract() != 0. {
+                    return Err(anyhow!("Expected an integer, found \"{}\"", token));
+                }
+                Ok(value)
+            }
+            BodyReader::Binary {
+                data,
+                position,
+                big_endian,
+            } => {
+                let size = scalar_type.size();
+                let mut bytes = data
+                    .get(*position..*position + size)
+                    .ok_or_else(|| anyhow!("PLY body ended early"))?
+                    .to_vec();
+                *position += size;
+                if *big_endian {
+                    bytes.reverse();
+                }
+                let array = |bytes: &[u8]| -> [u8; 8] {
+                    let mut array = [0; 8];
+                    array[..bytes.len()].copy_from_slice(bytes);
+                    array
+                };
+                let bytes = array(&bytes);
+                Ok(match scalar_type {
+                    ScalarType::Int8 => bytes[0] as i8 as f64,
+                    ScalarType::UInt8 => bytes[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::Int32 => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::UInt32 => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float32 => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+}
+
+// One instance of an element, lists are flattened after their count
+fn read_row(reader: &mut BodyReader, element: &Element) -> Result<Vec<Vec<f64>>> {
+    element
+        .properties
+        .iter()
+        .map(|property| match property.kind {
+            PropertyType::Scalar(scalar_type) => Ok(vec![reader.read(scalar_type)?]),
+            PropertyType::List(count_type, item_type) => {
+                let count = reader.read(count_type)?;
+                if count < 0. {
+                    return Err(anyhow!("Negative list length in \"{}\"", property.name));
+                }
+                (0..count as usize)
+                    .map(|_| reader.read(item_type))
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+// Positions of the vertex properties that map onto ObjFile
+struct VertexLayout {
+    position: [usize; 3],
+    normal: Option<[usize; 3]>,
+    color: Option<([usize; 3], bool)>,
+    texture: Option<[usize; 2]>,
+}
+
+impl VertexLayout {
+    fn new(element: &Element) -> Result<Self> {
+        let find = |names: &[&[&str]]| -> Option<Vec<usize>> {
+            names.iter().map(|names| element.property(names)).collect()
+        };
+        let position = find(&[&["x"], &["y"], &["z"]])
+            .ok_or_else(|| anyhow!("PLY vertex element needs x, y and z"))?;
+        let normal = find(&[&["nx"], &["ny"], &["nz"]]);
+        let color = find(&[&["red", "r"], &["green", "g"], &["blue", "b"]]);
+        let texture = find(&[
+            &["u", "s", "texture_u", "texture_s"],
+            &["v", "t", "texture_v", "texture_t"],
+        ]);
+        Ok(VertexLayout {
+            position: [position[0], position[1], position[2]],
+            normal: normal.map(|n| [n[0], n[1], n[2]]),
+            // Float colors are already in 0..1, integer ones are 0..255
+            color: color.map(|c| {
+                let is_float = matches!(
+                    element.properties[c[0]].kind,
+                    PropertyType::Scalar(scalar_type) if scalar_type.is_float()
+                );
+                ([c[0], c[1], c[2]], is_float)
+            }),
+            texture: texture.map(|t| [t[0], t[1]]),
+        })
+    }
+}
+
+pub fn parse_ply_bytes(bytes: &[u8]) -> Result<ObjFile> {
+    let header = parse_header(bytes)?;
+    let mut reader = BodyReader::new(header.format, &bytes[header.length..])?;
+    let mut obj = ObjFile::default();
+    let mut has_normals = false;
+    let mut has_texture_coords = false;
+    let mut raw_faces: Vec<Vec<f64>> = Vec::new();
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let layout = VertexLayout::new(element)?;
+                has_normals = layout.normal.is_some();
+                has_texture_coords = layout.texture.is_some();
+                for _ in 0..element.count {
+                    let row = read_row(&mut reader, element)?;
+                    let vector = |indices: [usize; 3]| Vector3::new(indices.map(|i| row[i][0]));
+                    let color = layout.color.map(|(indices, is_float)| {
+                        let scale = if is_float { 1. } else { 1. / 255. };
+                        vector(indices) * scale
+                    });
+                    obj.push_vertex(vector(layout.position), color);
+                    if let Some(indices) = layout.normal {
+                        obj.normals.push(vector(indices));
+                    }
+                    if let Some([u, v]) = layout.texture {
+                        obj.texture_coords
+                            .push(Vector3::new([row[u][0], row[v][0], 0.]));
+                    }
+                }
+            }
+            "face" => {
+                let indices = element
+                    .property(&["vertex_indices", "vertex_index"])
+                    .ok_or_else(|| anyhow!("PLY face element needs vertex_indices"))?;
+                for _ in 0..element.count {
+                    let mut row = read_row(&mut reader, element)?;
+                    raw_faces.push(std::mem::take(&mut row[indices]));
+                }
+            }
+            // Anything else is read past and dropped
+            _ => {
+                for _ in 0..element.count {
+                    read_row(&mut reader, element)?;
+                }
+            }
+        }
+    }
+
+    let vertex_count = obj.verticies.len();
+    for (face_index, raw_face) in raw_faces.into_iter().enumerate() {
+        if raw_face.len() < 3 {
+            return Err(anyhow!(
+                "PLY face {} needs at least 3 verticies, found {}",
+                face_index,
+                raw_face.len()
+            ));
+        }
+        let verticies = raw_face
+            .into_iter()
+            .map(|index| {
+                if index < 0. || index as usize >= vertex_count {
+                    return Err(anyhow!(
+                        "PLY face {} vertex index {} out of range, {} defined",
+                        face_index,
+                        index,
+                        vertex_count
+                    ));
+                }
+                let vertex = index as usize;
+                // PLY attributes are per vertex, so they share the vertex index
+                Ok(FaceVertex {
+                    vertex,
+                    texture: has_texture_coords.then_some(vertex),
+                    normal: has_normals.then_some(vertex),
+                    tangent: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        obj.faces.push(Face { verticies });
+    }
+    obj.triangulate();
+    Ok(obj)
+}
+
+pub fn parse_ply_file(path: &Path) -> Result<ObjFile> {
+    let bytes = std::fs::read(path)?;
+    parse_ply_bytes(&bytes)
+        .map_err(|e| anyhow!("Failed to read PLY file {}: {}", path.display(), e))
+}
+
+// PLY keeps one texture coordinate and normal per vertex, OBJ has them per face corner.
+// A vertex used with several different attributes is split into one PLY vertex each.
+struct SplitVerticies {
+    corners: Vec<FaceVertex>,
+    faces: Vec<Vec<usize>>,
+}
+
+impl SplitVerticies {
+    fn new(obj: &ObjFile) -> Result<Self> {
+        let mut corners: Vec<FaceVertex> = (0..obj.verticies.len()).map(FaceVertex::new).collect();
+        let mut used = vec![false; obj.verticies.len()];
+        let mut splits: HashMap<FaceVertex, usize> = HashMap::new();
+        let faces = obj
+            .faces
+            .iter()
+            .map(|face| {
+                face.verticies
+                    .iter()
+                    .map(|&corner| {
+                        if corner.vertex >= used.len() {
+                            return Err(anyhow!("Vertex index {} out of range", corner.vertex));
+                        }
+                        if !used[corner.vertex] {
+                            used[corner.vertex] = true;
+                            corners[corner.vertex] = corner;
+                            return Ok(corner.vertex);
+                        }
+                        if corners[corner.vertex] == corner {
+                            return Ok(corner.vertex);
+                        }
+                        Ok(*splits.entry(corner).or_insert_with(|| {
+                            corners.push(corner);
+                            corners.len() - 1
+                        }))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+        Ok(SplitVerticies { corners, faces })
+    }
+}
+
+fn write_scalar<W: Write>(out: &mut W, format: PlyFormat, value: PlyValue) -> Result<()> {
+    match format {
+        PlyFormat::Ascii => match value {
+            PlyValue::Double(value) => write!(out, "{}", value)?,
+            PlyValue::UChar(value) => write!(out, "{}", value)?,
+            PlyValue::Int(value) => write!(out, "{}", value)?,
+        },
+        PlyFormat::BinaryLittleEndian => match value {
+            PlyValue::Double(value) => out.write_all(&value.to_le_bytes())?,
+            PlyValue::UChar(value) => out.write_all(&[value])?,
+            PlyValue::Int(value) => out.write_all(&value.to_le_bytes())?,
+        },
+        PlyFormat::BinaryBigEndian => match value {
+            PlyValue::Double(value) => out.write_all(&value.to_be_bytes())?,
+            PlyValue::UChar(value) => out.write_all(&[value])?,
+            PlyValue::Int(value) => out.write_all(&value.to_be_bytes())?,
+        },
+    }
+    Ok(())
+}
+
+// The types the writer uses: double, uchar and int.
+// Doubles keep the full precision of the f64 data, floats would round large coordinates.
+#[derive(Clone, Copy)]
+enum PlyValue {
+    Double(f64),
+    UChar(u8),
+    Int(i32),
+}
+
+fn write_row<W: Write>(out: &mut W, format: PlyFormat, values: &[PlyValue]) -> Result<()> {
+    for (index, &value) in values.iter().enumerate() {
+        if format == PlyFormat::Ascii && index > 0 {
+            write!(out, " ")?;
+        }
+        write_scalar(out, format, value)?;
+    }
+    if format == PlyFormat::Ascii {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn write_ply<W: Write>(obj: &ObjFile, out: &mut W, format: PlyFormat) -> Result<()> {
+    let split = SplitVerticies::new(obj)?;
+    let has_normals = split.corners.iter().any(|corner| corner.normal.is_some());
+    let has_texture_coords = split.corners.iter().any(|corner| corner.texture.is_some());
+    let has_colors = !obj.vertex_colors.is_empty();
+
+    writeln!(out, "ply")?;
+    writeln!(out, "format {} 1.0", format.header_name())?;
+    writeln!(out, "element vertex {}", split.corners.len())?;
+    for name in ["x", "y", "z"] {
+        writeln!(out, "property double {}", name)?;
+    }
+    if has_normals {
+        for name in ["nx", "ny", "nz"] {
+            writeln!(out, "property double {}", name)?;
+        }
+    }
+    if has_texture_coords {
+        for name in ["u", "v"] {
+            writeln!(out, "property double {}", name)?;
+        }
+    }
+    if has_colors {
+        for name in ["red", "green", "blue"] {
+            writeln!(out, "property uchar {}", name)?;
+        }
+    }
+    writeln!(out, "element face {}", split.faces.len())?;
+    writeln!(out, "property list uchar int vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    let zero = Vector3::new([0., 0., 0.]);
+    let doubles = |vector: &Vector3<f64>, components: usize| {
+        vector.get_data()[..components]
+            .iter()
+            .map(|&component| PlyValue::Double(component))
+            .collect::<Vec<_>>()
+    };
+    for corner in &split.corners {
+        let mut values = doubles(&obj.verticies[corner.vertex], 3);
+        if has_normals {
+            let normal = corner.normal.and_then(|i| obj.normals.get(i));
+            values.extend(doubles(normal.unwrap_or(&zero), 3));
+        }
+        if has_texture_coords {
+            let texture_coord = corner.texture.and_then(|i| obj.texture_coords.get(i));
+            values.extend(doubles(texture_coord.unwrap_or(&zero), 2));
+        }
+        if has_colors {
+            let color = &obj.vertex_colors[corner.vertex];
+            values.extend(
+                color
+                    .get_data()
+                    .iter()
+                    .map(|&channel| PlyValue::UChar((channel.clamp(0., 1.) * 255.).round() as u8)),
+            );
+        }
+        write_row(out, format, &values)?;
+    }
+    for face in &split.faces {
+        let count = u8::try_from(face.len())
+            .map_err(|_| anyhow!("PLY faces can have at most 255 verticies"))?;
+        let mut values = vec![PlyValue::UChar(count)];
+        for &vertex in face {
+            values.push(PlyValue::Int(i32::try_from(vertex)?));
+        }
+        write_row(out, format, &values)?;
+    }
+    Ok(())
+}
+
+pub fn write_ply_file(obj: &ObjFile, path: &Path, format: PlyFormat) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_ply(obj, &mut out, format)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        math::Vector3,
+        obj::parse_obj_file,
+        ply::{PlyFormat, parse_ply_bytes, write_ply},
+    };
+
+    const CUBE_CORNER: &str = "ply
+format ascii 1.0
+comment a quad and a triangle
+element vertex 5
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+property float nx
+property float ny
+property float nz
+element face 2
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0 255 0 0 0 0 1
+1 0 0 0 255 0 0 0 1
+1 1 0 0 0 255 0 0 1
+0 1 0 255 255 255 0 0 1
+0.5 0.5 1 0 0 0 0 1 0
+4 0 1 2 3
+3 0 1 4
+0 1
+";
+
+    #[test]
+    fn ply_ascii() {
+        let obj = parse_ply_bytes(CUBE_CORNER.as_bytes()).unwrap();
+        assert_eq!(5, obj.verticies.len());
+        assert_eq!(Vector3::new([0.5, 0.5, 1.]), obj.verticies[4]);
+        assert_eq!(Vector3::new([0., 1., 0.]), obj.vertex_colors[1]);
+        assert_eq!(Vector3::new([0., 1., 0.]), obj.normals[4]);
+        assert!(obj.texture_coords.is_empty());
+        assert_eq!(2, obj.faces.len());
+        assert_eq!(4, obj.faces[0].verticies.len());
+        assert_eq!(Some(2), obj.faces[0].verticies[2].normal);
+        assert_eq!(None, obj.faces[0].verticies[2].texture);
+        assert_eq!(3, obj.triangles.len());
+    }
+
+    #[test]
+    fn ply_invalid() {
+        let bad_index = CUBE_CORNER.replace("3 0 1 4", "3 0 1 5");
+        assert!(parse_ply_bytes(bad_index.as_bytes()).is_err());
+        let truncated = CUBE_CORNER.replace("0 1\n", "");
+        assert!(parse_ply_bytes(truncated.as_bytes()).is_err());
+        let bad_type = CUBE_CORNER.replace("float x", "quad x");
+        assert!(parse_ply_bytes(bad_type.as_bytes()).is_err());
+        assert!(parse_ply_bytes(b"obj\n").is_err());
+
+        let mut obj = parse_ply_bytes(CUBE_CORNER.as_bytes()).unwrap();
+        obj.faces[1].verticies[2].vertex = 5;
+        let err = write_ply(&obj, &mut Vec::new(), PlyFormat::Ascii)
+            .err()
+            .unwrap();
+        assert_eq!("Vertex index 5 out of range", err.to_string());
+    }
+
+    #[test]
+    fn ply_round_trip() {
+        let expected = parse_ply_bytes(CUBE_CORNER.as_bytes()).unwrap();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let mut out: Vec<u8> = Vec::new();
+            write_ply(&expected, &mut out, format).unwrap();
+            let res = parse_ply_bytes(&out).unwrap();
+            assert_eq!(expected.verticies, res.verticies);
+            assert_eq!(expected.vertex_colors, res.vertex_colors);
+            assert_eq!(expected.normals, res.normals);
+            assert_eq!(expected.faces, res.faces);
+        }
+    }
+
+    #[test]
+    fn ply_keeps_double_precision() {
+        let mut expected = parse_ply_bytes(CUBE_CORNER.as_bytes()).unwrap();
+        expected.verticies[4] = Vector3::new([123456789.125, -0.1, 6378137.000001]);
+        expected.normals[4] = Vector3::new([0.1, 0.2, 0.3]);
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+            let mut out: Vec<u8> = Vec::new();
+            write_ply(&expected, &mut out, format).unwrap();
+            let res = parse_ply_bytes(&out).unwrap();
+            assert_eq!(expected.verticies, res.verticies);
+            assert_eq!(expected.normals, res.normals);
+        }
+    }
+
+    #[test]
+    fn ply_splits_obj_corners() {
+        let expected = parse_obj_file(Path::new("./assets/diablo.obj")).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        write_ply(&expected, &mut out, PlyFormat::BinaryLittleEndian).unwrap();
+        let res = parse_ply_bytes(&out).unwrap();
+
+        assert!(res.verticies.len() >= expected.verticies.len());
+        assert_eq!(expected.faces.len(), res.faces.len());
+        for (expected_face, res_face) in expected.faces.iter().zip(&res.faces) {
+            for (a, b) in expected_face.verticies.iter().zip(&res_face.verticies) {
+                let difference =
+                    expected.verticies[a.vertex].clone() - res.verticies[b.vertex].clone();
+                assert!(difference.dot(&difference) < 1e-10);
+                let (Some(a_uv), Some(b_uv)) = (a.texture, b.texture) else {
+                    panic!("texture coordinates missing");
+                };
+                // PLY has no w
+                let (a_uv, b_uv) = (&expected.texture_coords[a_uv], &res.texture_coords[b_uv]);
+                assert!((a_uv.x() - b_uv.x()).abs() < 1e-6);
+                assert!((a_uv.y() - b_uv.y()).abs() < 1e-6);
+            }
+        }
+    }
+}
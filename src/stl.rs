@@ -0,0 +1,337 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::{
+    math::Vector3,
+    obj::ObjFile,
+    types::{Face, FaceVertex},
+};
+
+const HEADER_LENGTH: usize = 80;
+const TRIANGLE_LENGTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StlFormat {
+    Ascii,
+    #[default]
+    Binary,
+}
+
+fn unit_normal(points: [&Vector3<f64>; 3]) -> Vector3<f64> {
+    (points[1].clone() - points[0].clone())
+        .cross(&(points[2].clone() - points[0].clone()))
+        .normalize()
+}
+
+// STL repeats every vertex for each facet touching it. Bitwise equal positions
+// become one vertex so the mesh is connected like an OBJ.
+struct Welder {
+    obj: ObjFile,
+    indices: HashMap<[u64; 3], usize>,
+}
+
+impl Welder {
+    fn new() -> Self {
+        Welder {
+            obj: ObjFile::default(),
+            indices: HashMap::new(),
+        }
+    }
+
+    fn vertex(&mut self, position: Vector3<f64>) -> usize {
+        // -0 and 0 are the same point
+        let key = position
+            .get_data()
+            .map(|component| (component + 0.).to_bits());
+        *self.indices.entry(key).or_insert_with(|| {
+            self.obj.verticies.push(position);
+            self.obj.verticies.len() - 1
+        })
+    }
+
+    // Zero normals, which many exporters write, are rebuilt from the winding
+    fn facet(&mut self, normal: Vector3<f64>, positions: Vec<Vector3<f64>>) {
+        let normal = if normal.dot(&normal) == 0. {
+            unit_normal([&positions[0], &positions[1], &positions[2]])
+        } else {
+            normal
+        };
+        self.obj.normals.push(normal);
+        let normal_index = self.obj.normals.len() - 1;
+        let verticies = positions
+            .into_iter()
+            .map(|position| FaceVertex {
+                vertex: self.vertex(position),
+                texture: None,
+                normal: Some(normal_index),
+                tangent: None,
+            })
+            .collect();
+        self.obj.faces.push(Face { verticies });
+    }
+
+    fn finish(mut self) -> ObjFile {
+        self.obj.triangulate();
+        self.obj
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() < HEADER_LENGTH + 4 {
+        return false;
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    // Some binary exporters start the header with "solid" too, so go by the size
+    bytes.len() == HEADER_LENGTH + 4 + count * TRIANGLE_LENGTH
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<ObjFile> {
+    let mut welder = Welder::new();
+    for triangle in bytes[HEADER_LENGTH + 4..].chunks_exact(TRIANGLE_LENGTH) {
+        let vector = |offset: usize| {
+            Vector3::new([0, 1, 2].map(|i| {
+                let start = offset + i * 4;
+                f32::from_le_bytes([
+                    triangle[start],
+                    triangle[start + 1],
+                    triangle[start + 2],
+                    triangle[start + 3],
+                ]) as f64
+            }))
+        };
+        // The two attribute bytes at the end are ignored
+        welder.facet(vector(0), vec![vector(12), vector(24), vector(36)]);
+    }
+    Ok(welder.finish())
+}
+
+fn parse_ascii(stl_str: &str) -> Result<ObjFile> {
+    let mut welder = Welder::new();
+    let mut normal = None;
+    let mut positions = Vec::new();
+    for (index, line) in stl_str.lines().enumerate() {
+        let error = |reason: String| anyhow!("STL line {}: {}", index + 1, reason);
+        let vector = |args: &[&str]| -> Result<Vector3<f64>> {
+            let components = args
+                .iter()
+                .map(|arg| arg.parse::<f64>().ok())
+                .collect::<Option<Vec<f64>>>()
+                .filter(|components| components.len() == 3)
+                .ok_or_else(|| {
+                    error(format!("Expected 3 numbers, found \"{}\"", args.join(" ")))
+                })?;
+            Ok(Vector3::new([components[0], components[1], components[2]]))
+        };
+        let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+        match tokens.as_slice() {
+            [] | ["solid", ..] | ["endsolid", ..] | ["outer", "loop"] | ["endloop"] => {}
+            ["facet", "normal", args @ ..] => {
+                normal = Some(vector(args)?);
+                positions.clear();
+            }
+            ["vertex", args @ ..] => {
+                if normal.is_none() {
+                    return Err(error("Vertex outside of a facet".to_string()));
+                }
+                positions.push(vector(args)?);
+            }
+            ["endfacet"] => {
+                let normal = normal
+                    .take()
+                    .ok_or_else(|| error("endfacet without facet".to_string()))?;
+                if positions.len() < 3 {
+                    return Err(error(format!(
+                        "Facet needs at least 3 verticies, found {}",
+                        positions.len()
+                    )));
+                }
+                welder.facet(normal, std::mem::take(&mut positions));
+            }
+            _ => return Err(error(format!("Unexpected \"{}\"", line.trim()))),
+        }
+    }
+    if normal.is_some() {
+        return Err(anyhow!("STL ended inside a facet"));
+    }
+    Ok(welder.finish())
+}
+
+pub fn parse_stl_bytes(bytes: &[u8]) -> Result<ObjFile> {
+    if is_binary(bytes) {
+        return parse_binary(bytes);
+    }
+    let stl_str = std::str::from_utf8(bytes)
+        .map_err(|_| anyhow!("Not an STL file, binary size doesn't match its triangle count"))?;
+    if !stl_str.trim_start().starts_with("solid") {
+        return Err(anyhow!("Not an STL file"));
+    }
+    parse_ascii(stl_str)
+}
+
+pub fn parse_stl_file(path: &Path) -> Result<ObjFile> {
+    let bytes = std::fs::read(path)?;
+    parse_stl_bytes(&bytes)
+        .map_err(|e| anyhow!("Failed to read STL file {}: {}", path.display(), e))
+}
+
+// Writes `obj.triangles`, with normals worked out from the winding
+pub fn write_stl<W: Write>(obj: &ObjFile, out: &mut W, format: StlFormat) -> Result<()> {
+    let position = |corner: &FaceVertex| {
+        obj.verticies
+            .get(corner.vertex)
+            .ok_or_else(|| anyhow!("Vertex index {} out of range", corner.vertex))
+    };
+    let mut triangles = Vec::with_capacity(obj.triangles.len());
+    for triangle in &obj.triangles {
+        let points = [
+            position(&triangle.one)?,
+            position(&triangle.two)?,
+            position(&triangle.three)?,
+        ];
+        triangles.push((unit_normal(points), points));
+    }
+
+    match format {
+        StlFormat::Ascii => {
+            writeln!(out, "solid mesh")?;
+            for (normal, points) in &triangles {
+                let data = normal.get_data();
+                writeln!(
+                    out,
+                    "  facet normal {:e} {:e} {:e}",
+                    data[0], data[1], data[2]
+                )?;
+                writeln!(out, "    outer loop")?;
+                for point in points {
+                    let data = point.get_data();
+                    writeln!(
+                        out,
+                        "      vertex {:e} {:e} {:e}",
+                        data[0], data[1], data[2]
+                    )?;
+                }
+                writeln!(out, "    endloop")?;
+                writeln!(out, "  endfacet")?;
+            }
+            writeln!(out, "endsolid mesh")?;
+        }
+        StlFormat::Binary => {
+            let mut header = [0u8; HEADER_LENGTH];
+            let name = b"binary STL from tiny_renderer";
+            header[..name.len()].copy_from_slice(name);
+            out.write_all(&header)?;
+            let count = u32::try_from(triangles.len())
+                .map_err(|_| anyhow!("Too many triangles for a binary STL"))?;
+            out.write_all(&count.to_le_bytes())?;
+            for (normal, points) in &triangles {
+                for vector in [normal].into_iter().chain(points.iter().copied()) {
+                    for component in vector.get_data() {
+                        out.write_all(&(component as f32).to_le_bytes())?;
+                    }
+                }
+                out.write_all(&[0, 0])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn write_stl_file(obj: &ObjFile, path: &Path, format: StlFormat) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_stl(obj, &mut out, format)?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        math::Vector3,
+        obj::{ObjParseOptions, parse_obj_str},
+        stl::{StlFormat, parse_stl_bytes, write_stl},
+    };
+
+    const TETRAHEDRON: &str = "solid tetrahedron
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 0 1 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 0 1
+    endloop
+  endfacet
+  facet normal -1 0 0
+    outer loop
+      vertex 0 0 0
+      vertex 0 0 1
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0.577 0.577 0.577
+    outer loop
+      vertex 1 0 0
+      vertex 0 1 0
+      vertex 0 0 1
+    endloop
+  endfacet
+endsolid tetrahedron
+";
+
+    #[test]
+    fn stl_ascii_welds_verticies() {
+        let obj = parse_stl_bytes(TETRAHEDRON.as_bytes()).unwrap();
+        assert_eq!(4, obj.verticies.len());
+        assert_eq!(4, obj.faces.len());
+        assert_eq!(4, obj.triangles.len());
+        // Same corner in every facet
+        assert!(
+            obj.faces
+                .iter()
+                .take(3)
+                .all(|face| face.verticies[0].vertex == 0)
+        );
+        // The zero normal is rebuilt from the winding
+        assert_eq!(Vector3::new([0., -1., 0.]), obj.normals[1]);
+        assert_eq!(Some(3), obj.faces[3].verticies[2].normal);
+    }
+
+    #[test]
+    fn stl_invalid() {
+        let missing_vertex = TETRAHEDRON.replacen("      vertex 0 0 1\n", "", 1);
+        assert!(parse_stl_bytes(missing_vertex.as_bytes()).is_err());
+        let unfinished = TETRAHEDRON.replace("  endfacet\nendsolid tetrahedron\n", "");
+        assert!(parse_stl_bytes(unfinished.as_bytes()).is_err());
+        assert!(parse_stl_bytes(&[0; 90]).is_err());
+    }
+
+    #[test]
+    fn stl_round_trip() {
+        let obj_str = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nv 1 1 1.5\nf 4 3 2 1\nf 1 2 5\nf 2 3 5\nf 3 4 5\nf 4 1 5\n";
+        let expected = parse_obj_str(obj_str, &ObjParseOptions::default()).unwrap();
+        for format in [StlFormat::Ascii, StlFormat::Binary] {
+            let mut out: Vec<u8> = Vec::new();
+            write_stl(&expected, &mut out, format).unwrap();
+            let res = parse_stl_bytes(&out).unwrap();
+            assert_eq!(5, res.verticies.len());
+            assert_eq!(expected.triangles.len(), res.faces.len());
+            for (a, b) in expected.triangles.iter().zip(&res.triangles) {
+                for (a, b) in [(a.one, b.one), (a.two, b.two), (a.three, b.three)] {
+                    assert_eq!(expected.verticies[a.vertex], res.verticies[b.vertex]);
+                }
+            }
+            assert_eq!(Vector3::new([0., 0., -1.]), res.normals[0]);
+        }
+    }
+}
@@ -2,8 +2,7 @@
 use anyhow::Result;
 use anyhow::anyhow;
 use rand::Rng;
-use std::io::{BufWriter, prelude::*};
-use std::u8;
+use std::io::{BufReader, BufWriter, prelude::*};
 use std::{fs::File, io};
 
 use rand::rng;
@@ -17,6 +16,8 @@ unsafe fn any_as_u8_slice<T: Sized>(p: &T) -> &[u8] {
 pub trait ColorSpace {
     fn new() -> Self;
     fn random() -> Self;
+    // TGA stores pixels as BGR(A), readers convert through this
+    fn from_bgra(b: u8, g: u8, r: u8, a: u8) -> Self;
     const BPP: u8;
 }
 
@@ -48,6 +49,12 @@ impl ColorSpace for Grayscale {
         let rand_val: u8 = rng.random();
         Grayscale { i: rand_val }
     }
+    fn from_bgra(b: u8, g: u8, r: u8, _a: u8) -> Self {
+        let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+        Grayscale {
+            i: luma.round() as u8,
+        }
+    }
     const BPP: u8 = 1;
 }
 impl ColorSpace for RGB {
@@ -66,6 +73,9 @@ impl ColorSpace for RGB {
             b: rand_b,
         }
     }
+    fn from_bgra(b: u8, g: u8, r: u8, _a: u8) -> Self {
+        RGB { b, g, r }
+    }
     const BPP: u8 = 3;
 }
 impl ColorSpace for RGBA {
@@ -90,9 +100,13 @@ impl ColorSpace for RGBA {
             a: 1,
         }
     }
+    fn from_bgra(b: u8, g: u8, r: u8, a: u8) -> Self {
+        RGBA { b, g, r, a }
+    }
     const BPP: u8 = 4;
 }
 
+#[derive(Clone)]
 pub struct Image<T: ColorSpace> {
     pub width: usize,
     pub height: usize,
@@ -103,9 +117,10 @@ const DEVELOPER_AREA_REF: [u8; 4] = [0, 0, 0, 0];
 const EXTENSION_AREA_REF: [u8; 4] = [0, 0, 0, 0];
 const FOOTER: &[u8; 18] = b"TRUEVISION-XFILE.\0";
 const MAX_CHUNK_LENGTH: u8 = 128;
+const HEADER_LENGTH: usize = 18;
 
 #[derive(Default)]
-#[repr(packed)]
+#[repr(C, packed)]
 #[allow(dead_code)]
 struct Header {
     idlength: u8,
@@ -239,4 +254,180 @@ impl<T: ColorSpace + Copy> Image<T> {
             .expect("Error writing footer to TGA file");
         Ok(())
     }
+
+    // Reads uncompressed or RLE true color and grayscale TGA files.
+    // Rows are stored bottom up like the writer does with vflip, so y = 0 is the bottom row.
+    pub fn read_from_file(filename: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(filename)?).read_to_end(&mut bytes)?;
+        Self::read_from_bytes(&bytes)
+    }
+
+    pub fn read_from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_LENGTH {
+            return Err(anyhow!("TGA data is too short for a header"));
+        }
+        let id_length = bytes[0] as usize;
+        let colormap_type = bytes[1];
+        let datatype_code = bytes[2];
+        let colormap_length = u16::from_le_bytes([bytes[5], bytes[6]]) as usize;
+        let colormap_depth = bytes[7] as usize;
+        let width = u16::from_le_bytes([bytes[12], bytes[13]]) as usize;
+        let height = u16::from_le_bytes([bytes[14], bytes[15]]) as usize;
+        let bits_per_pixel = bytes[16];
+        let image_descriptor = bytes[17];
+        if width == 0 || height == 0 {
+            return Err(anyhow!("TGA image has no pixels ({}x{})", width, height));
+        }
+
+        let rle = match datatype_code {
+            2 | 3 => false,
+            10 | 11 => true,
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported TGA data type {}, only true color and grayscale images can be read",
+                    datatype_code
+                ));
+            }
+        };
+        let bytes_per_pixel = match bits_per_pixel {
+            8 | 24 | 32 => (bits_per_pixel / 8) as usize,
+            _ => return Err(anyhow!("Unsupported TGA pixel depth {}", bits_per_pixel)),
+        };
+        let colormap_size = if colormap_type == 0 {
+            0
+        } else {
+            colormap_length * colormap_depth.div_ceil(8)
+        };
+
+        let pixel_data = bytes
+            .get(HEADER_LENGTH + id_length + colormap_size..)
+            .ok_or_else(|| anyhow!("TGA data ends before the pixel data"))?;
+        let n_pixels = width * height;
+        let raw = if rle {
+            decode_rle(pixel_data, n_pixels, bytes_per_pixel)?
+        } else {
+            pixel_data
+                .get(..n_pixels * bytes_per_pixel)
+                .ok_or_else(|| anyhow!("TGA pixel data is truncated"))?
+                .to_vec()
+        };
+
+        let pixels = raw.chunks_exact(bytes_per_pixel).map(|p| match p {
+            [i] => T::from_bgra(*i, *i, *i, 255),
+            [b, g, r] => T::from_bgra(*b, *g, *r, 255),
+            [b, g, r, a] => T::from_bgra(*b, *g, *r, *a),
+            _ => unreachable!(),
+        });
+        let mut data: Vec<T> = pixels.collect();
+        // Top left origin, flip so rows run bottom up
+        if image_descriptor & 0x20 != 0 {
+            data = data.chunks_exact(width).rev().flatten().copied().collect();
+        }
+        Ok(Image {
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+fn decode_rle(data: &[u8], n_pixels: usize, bytes_per_pixel: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(n_pixels * bytes_per_pixel);
+    let mut pos = 0;
+    let truncated = || anyhow!("TGA RLE data is truncated");
+    while out.len() < n_pixels * bytes_per_pixel {
+        let packet = *data.get(pos).ok_or_else(truncated)?;
+        pos += 1;
+        let count = (packet & 0x7f) as usize + 1;
+        if packet & 0x80 != 0 {
+            let pixel = data.get(pos..pos + bytes_per_pixel).ok_or_else(truncated)?;
+            for _ in 0..count {
+                out.extend_from_slice(pixel);
+            }
+            pos += bytes_per_pixel;
+        } else {
+            let length = count * bytes_per_pixel;
+            out.extend_from_slice(data.get(pos..pos + length).ok_or_else(truncated)?);
+            pos += length;
+        }
+    }
+    out.truncate(n_pixels * bytes_per_pixel);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tga::{Image, RGB};
+
+    fn gradient(width: usize, height: usize) -> Image<RGB> {
+        let mut img = Image::<RGB>::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = RGB {
+                    r: (x * 10) as u8,
+                    g: (y * 10) as u8,
+                    b: 7,
+                };
+                img.set_pixel(x, y, color).unwrap();
+            }
+        }
+        img
+    }
+
+    fn assert_same(expected: &Image<RGB>, res: &Image<RGB>) {
+        assert_eq!(expected.width, res.width);
+        assert_eq!(expected.height, res.height);
+        for y in 0..expected.height {
+            for x in 0..expected.width {
+                let (a, b) = (
+                    expected.get_pixel(x, y).unwrap(),
+                    res.get_pixel(x, y).unwrap(),
+                );
+                assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+            }
+        }
+    }
+
+    #[test]
+    fn tga_round_trip() {
+        let img = gradient(9, 5);
+        for (rle, vflip) in [(false, true), (true, true), (true, false)] {
+            let path =
+                std::env::temp_dir().join(format!("tiny_renderer_tga_{}_{}.tga", rle, vflip));
+            let filename = path.to_str().unwrap();
+            img.write_to_file(filename, vflip, rle).unwrap();
+            let res = Image::<RGB>::read_from_file(filename).unwrap();
+            let _ = std::fs::remove_file(&path);
+            if vflip {
+                assert_same(&img, &res);
+            } else {
+                // Written top down, so reading it back flips it
+                let mut flipped = Image::<RGB>::new(img.width, img.height);
+                for y in 0..img.height {
+                    for x in 0..img.width {
+                        let pixel = *img.get_pixel(x, img.height - 1 - y).unwrap();
+                        flipped.set_pixel(x, y, pixel).unwrap();
+                    }
+                }
+                assert_same(&flipped, &res);
+            }
+        }
+    }
+
+    #[test]
+    fn tga_truncated() {
+        assert!(Image::<RGB>::read_from_bytes(&[0; 10]).is_err());
+    }
+
+    #[test]
+    fn tga_zero_size() {
+        // Uncompressed true color, 0x2 pixels, top left origin
+        let mut header = [0u8; 18];
+        header[2] = 2;
+        header[14] = 2;
+        header[16] = 24;
+        header[17] = 0x20;
+        assert!(Image::<RGB>::read_from_bytes(&header).is_err());
+    }
 }
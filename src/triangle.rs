@@ -34,7 +34,33 @@ impl Triangle {
         &self,
         color: T,
         img: &mut Image<T>,
+        z_buff_opt: Option<&mut Vec<Vec<f64>>>,
+    ) -> Result<()> {
+        self.rasterize(img, z_buff_opt, |_| color)
+    }
+
+    // Blends the RGB colors (0..1) of the three verticies across the triangle
+    pub fn draw_interpolated<T: ColorSpace + Copy>(
+        &self,
+        colors: [&Vector3<f64>; 3],
+        img: &mut Image<T>,
+        z_buff_opt: Option<&mut Vec<Vec<f64>>>,
+    ) -> Result<()> {
+        self.rasterize(img, z_buff_opt, |weights| {
+            let channel = |i: usize| {
+                let value: f64 = (0..3).map(|v| weights[v] * colors[v][i]).sum();
+                (value.clamp(0., 1.) * 255.).round() as u8
+            };
+            T::from_bgra(channel(2), channel(1), channel(0), 255)
+        })
+    }
+
+    // `shade` gets the barycentric weights of verticies a, b and c for each pixel
+    fn rasterize<T: ColorSpace + Copy>(
+        &self,
+        img: &mut Image<T>,
         mut z_buff_opt: Option<&mut Vec<Vec<f64>>>,
+        shade: impl Fn([f64; 3]) -> T,
     ) -> Result<()> {
         let bb_min_x = self
             .vector_a
@@ -88,10 +114,10 @@ impl Triangle {
                         // Direct index feels risky, but it should be safe.
                         if z > z_buffer[x_unsigned][y_unsigned] {
                             z_buffer[x_unsigned][y_unsigned] = z;
-                            img.set_pixel(x_unsigned, y_unsigned, color)?;
+                            img.set_pixel(x_unsigned, y_unsigned, shade([alpha, beta, gamma]))?;
                         }
                     } else {
-                        img.set_pixel(x_unsigned, y_unsigned, color)?;
+                        img.set_pixel(x_unsigned, y_unsigned, shade([alpha, beta, gamma]))?;
                     }
                 }
             }
@@ -148,6 +174,31 @@ mod test {
         let _ = triangle_2.draw(Color::White.rgba_value(), &mut img, None);
         let _ = triangle_3.draw(Color::Green.rgba_value(), &mut img, None);
 
-        let _ = img.write_to_file("triangles.tga", true, true);
+        let path = std::env::temp_dir().join("tiny_renderer_triangles.tga");
+        let _ = img.write_to_file(path.to_str().unwrap(), true, true);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_triangle_interpolated() {
+        let mut img = Image::<RGBA>::new(64, 64);
+        let triangle = Triangle {
+            vector_a: Vector3::new([0, 0, 0]),
+            vector_b: Vector3::new([63, 0, 0]),
+            vector_c: Vector3::new([0, 63, 0]),
+        };
+        let red = Vector3::new([1., 0., 0.]);
+        let green = Vector3::new([0., 1., 0.]);
+        let blue = Vector3::new([0., 0., 1.]);
+        triangle
+            .draw_interpolated([&red, &green, &blue], &mut img, None)
+            .unwrap();
+
+        let corner = img.get_pixel(0, 0).unwrap();
+        assert_eq!((255, 0, 0), (corner.r, corner.g, corner.b));
+        let middle = img.get_pixel(20, 21).unwrap();
+        assert!(middle.r > 60 && middle.g > 60 && middle.b > 60);
+        let edge = img.get_pixel(62, 1).unwrap();
+        assert!(edge.g > 240 && edge.b < 10);
     }
 }
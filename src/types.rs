@@ -4,12 +4,46 @@ pub struct Point {
     pub y: isize,
 }
 
-// Indexes of verticies
-// Hard coding three ints. Will likely change down the line
-// Just need to parse an obj file for now
-#[derive(Debug)]
+// One corner of a face.
+// Indexes into the vertex, texture coordinate, normal and tangent lists of an ObjFile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FaceVertex {
+    pub vertex: usize,
+    pub texture: Option<usize>,
+    pub normal: Option<usize>,
+    // Only set by mesh::generate_tangents, OBJ files have no tangents
+    pub tangent: Option<usize>,
+}
+
+impl FaceVertex {
+    pub fn new(vertex: usize) -> Self {
+        FaceVertex {
+            vertex,
+            ..Default::default()
+        }
+    }
+}
+
+// Corners of a polygon, in winding order.
+// Can hold any number of verticies, quads and n-gons included.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Face {
-    pub one: usize,
-    pub two: usize,
-    pub three: usize,
+    pub verticies: Vec<FaceVertex>,
+}
+
+// Verticies of an "l" statement, joined in order.
+// Only `vertex` and `texture` are used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polyline {
+    pub verticies: Vec<FaceVertex>,
+}
+
+// The three corners of a triangle.
+// `face` is the index of the polygon the triangle was cut from.
+#[derive(Debug, Clone, Copy)]
+pub struct TriangleFace {
+    pub one: FaceVertex,
+    pub two: FaceVertex,
+    pub three: FaceVertex,
+    pub face: usize,
 }